@@ -0,0 +1,119 @@
+use crate::backend::key_utils::MouseButtonOrKey;
+use gilrs::Axis;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum AxisHalf {
+    Positive,
+    Negative,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AxisBinding {
+    pub axis: Axis,
+    pub half: AxisHalf,
+    pub press_threshold: f32,
+    // Keeping this below the press threshold stops the output from chattering around it.
+    pub release_threshold: f32,
+    pub output: Option<MouseButtonOrKey>,
+}
+
+impl AxisBinding {
+    // D-pad axes are left out since gilrs turns them into D-pad button events before they arrive.
+    pub const AXES: [Axis; 6] = [
+        Axis::LeftStickX,
+        Axis::LeftStickY,
+        Axis::LeftZ,
+        Axis::RightStickX,
+        Axis::RightStickY,
+        Axis::RightZ,
+    ];
+
+    pub fn new(axis: Axis, half: AxisHalf) -> Self {
+        Self {
+            axis,
+            half,
+            press_threshold: 0.5,
+            release_threshold: 0.3,
+            output: None,
+        }
+    }
+
+    pub fn options() -> Vec<AxisBinding> {
+        Self::AXES
+            .into_iter()
+            .flat_map(|axis| {
+                [
+                    AxisBinding::new(axis, AxisHalf::Positive),
+                    AxisBinding::new(axis, AxisHalf::Negative),
+                ]
+            })
+            .collect()
+    }
+
+    pub fn get_amount(&self, value: f32) -> f32 {
+        match self.half {
+            AxisHalf::Positive => value,
+            AxisHalf::Negative => -value,
+        }
+    }
+
+    pub fn is_pressed(&self, value: f32, is_held: bool) -> bool {
+        let amt = self.get_amount(value);
+        if is_held {
+            amt > self.release_threshold.min(self.press_threshold)
+        } else {
+            amt >= self.press_threshold
+        }
+    }
+}
+
+impl Display for AxisBinding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.half {
+            AxisHalf::Positive => write!(f, "{:?} +", self.axis),
+            AxisHalf::Negative => write!(f, "{:?} -", self.axis),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_pressed_at_press_threshold() {
+        let ab = AxisBinding::new(Axis::LeftZ, AxisHalf::Positive);
+        assert!(!ab.is_pressed(0.49, false));
+        assert!(ab.is_pressed(0.5, false));
+    }
+
+    #[test]
+    fn is_pressed_until_release_threshold() {
+        let ab = AxisBinding::new(Axis::LeftZ, AxisHalf::Positive);
+        assert!(ab.is_pressed(0.4, true));
+        assert!(ab.is_pressed(0.31, true));
+        assert!(!ab.is_pressed(0.3, true));
+        // Between the thresholds nothing changes.
+        assert!(!ab.is_pressed(0.4, false));
+    }
+
+    #[test]
+    fn is_pressed_for_negative_half() {
+        let ab = AxisBinding::new(Axis::LeftStickX, AxisHalf::Negative);
+        assert!(ab.is_pressed(-1.0, false));
+        assert!(!ab.is_pressed(1.0, false));
+        assert!(!ab.is_pressed(1.0, true));
+    }
+
+    #[test]
+    fn release_threshold_is_capped_at_press_threshold() {
+        let ab = AxisBinding {
+            release_threshold: 0.8,
+            ..AxisBinding::new(Axis::LeftZ, AxisHalf::Positive)
+        };
+        assert!(ab.is_pressed(0.6, true));
+        assert!(!ab.is_pressed(0.5, true));
+    }
+}
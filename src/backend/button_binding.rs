@@ -0,0 +1,295 @@
+use crate::backend::key_utils::MouseButtonOrKey;
+use crate::backend::macros::{Macro, MacroStep};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Turbo {
+    // Presses per second.
+    pub rate: f32,
+    // How much of each press cycle the output is held down for, between 0.0 and 1.0.
+    pub duty_cycle: f32,
+}
+
+impl Default for Turbo {
+    fn default() -> Self {
+        Self {
+            rate: 10.0,
+            duty_cycle: 0.5,
+        }
+    }
+}
+
+impl Turbo {
+    pub const MAX_RATE: f32 = 30.0;
+
+    fn to_macro(self, output: &MouseButtonOrKey) -> Macro {
+        let cycle_ms = 1000.0 / self.rate.max(0.1);
+        let held_ms = (cycle_ms * self.duty_cycle.clamp(0.0, 1.0)).round();
+        Macro {
+            steps: vec![
+                MacroStep::KeyDown(Some(output.clone())),
+                MacroStep::Wait(held_ms.max(1.0) as u64),
+                MacroStep::KeyUp(Some(output.clone())),
+                MacroStep::Wait((cycle_ms - held_ms).round().max(1.0) as u64),
+            ],
+            cancel_on_release: true,
+            repeat_while_held: true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HoldAction {
+    pub output: Option<MouseButtonOrKey>,
+    pub threshold_ms: u32,
+}
+
+impl Default for HoldAction {
+    fn default() -> Self {
+        Self {
+            output: None,
+            threshold_ms: 200,
+        }
+    }
+}
+
+impl HoldAction {
+    pub const MIN_THRESHOLD_MS: u32 = 50;
+    pub const MAX_THRESHOLD_MS: u32 = 1000;
+
+    pub fn threshold(&self) -> Duration {
+        Duration::from_millis(self.threshold_ms as u64)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MultiTap {
+    pub double_tap: Option<MouseButtonOrKey>,
+    pub triple_tap: Option<MouseButtonOrKey>,
+    // How soon after a tap the next one has to start to count towards the same multi-tap.
+    pub window_ms: u32,
+}
+
+impl Default for MultiTap {
+    fn default() -> Self {
+        Self {
+            double_tap: None,
+            triple_tap: None,
+            window_ms: 250,
+        }
+    }
+}
+
+impl MultiTap {
+    pub const MIN_WINDOW_MS: u32 = 100;
+    pub const MAX_WINDOW_MS: u32 = 1000;
+
+    pub fn window(&self) -> Duration {
+        Duration::from_millis(self.window_ms as u64)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ExtraOutput {
+    Hold,
+    DoubleTap,
+    TripleTap,
+}
+
+impl Display for ExtraOutput {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExtraOutput::Hold => write!(f, "held"),
+            ExtraOutput::DoubleTap => write!(f, "double tap"),
+            ExtraOutput::TripleTap => write!(f, "triple tap"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ButtonBinding {
+    pub output: MouseButtonOrKey,
+    #[serde(default)]
+    pub turbo: Option<Turbo>,
+    #[serde(default)]
+    pub toggle: bool,
+    // With a hold or multi-tap action the output above is only tapped, and turbo and toggle don't
+    // apply.
+    #[serde(default)]
+    pub hold: Option<HoldAction>,
+    #[serde(default)]
+    pub multi_tap: Option<MultiTap>,
+}
+
+impl ButtonBinding {
+    pub fn new(output: MouseButtonOrKey) -> Self {
+        Self {
+            output,
+            turbo: None,
+            toggle: false,
+            hold: None,
+            multi_tap: None,
+        }
+    }
+
+    pub fn is_delayed(&self) -> bool {
+        self.hold.is_some() || self.multi_tap.is_some()
+    }
+
+    pub fn max_taps(&self) -> usize {
+        match &self.multi_tap {
+            Some(MultiTap {
+                triple_tap: Some(_),
+                ..
+            }) => 3,
+            Some(MultiTap {
+                double_tap: Some(_),
+                ..
+            }) => 2,
+            _ => 1,
+        }
+    }
+
+    pub fn get_tap_output(&self, taps: usize) -> Option<&MouseButtonOrKey> {
+        match taps {
+            1 => Some(&self.output),
+            2 => self.multi_tap.as_ref()?.double_tap.as_ref(),
+            3 => self.multi_tap.as_ref()?.triple_tap.as_ref(),
+            _ => None,
+        }
+    }
+
+    pub fn get_extra_output(&self, extra: ExtraOutput) -> Option<&MouseButtonOrKey> {
+        match extra {
+            ExtraOutput::Hold => self.hold.as_ref()?.output.as_ref(),
+            ExtraOutput::DoubleTap => self.multi_tap.as_ref()?.double_tap.as_ref(),
+            ExtraOutput::TripleTap => self.multi_tap.as_ref()?.triple_tap.as_ref(),
+        }
+    }
+
+    pub fn set_extra_output(&mut self, extra: ExtraOutput, output: Option<MouseButtonOrKey>) {
+        match (extra, &mut self.hold, &mut self.multi_tap) {
+            (ExtraOutput::Hold, Some(hold), _) => hold.output = output,
+            (ExtraOutput::DoubleTap, _, Some(multi_tap)) => multi_tap.double_tap = output,
+            (ExtraOutput::TripleTap, _, Some(multi_tap)) => multi_tap.triple_tap = output,
+            _ => {}
+        }
+    }
+
+    // Macros, text and commands aren't held down like keys, so there's nothing to latch or repeat.
+    pub fn can_toggle(&self) -> bool {
+        !matches!(
+            self.output,
+            MouseButtonOrKey::Macro(_) | MouseButtonOrKey::Text(_) | MouseButtonOrKey::Command(_)
+        )
+    }
+
+    pub fn can_turbo(&self) -> bool {
+        self.can_toggle()
+    }
+
+    pub fn get_press_output(&self) -> MouseButtonOrKey {
+        match self.turbo {
+            Some(turbo) if self.can_turbo() => {
+                MouseButtonOrKey::Macro(turbo.to_macro(&self.output))
+            }
+            _ => self.output.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use enigo::{Button, Key};
+
+    fn key(c: char) -> MouseButtonOrKey {
+        MouseButtonOrKey::Key(Key::Unicode(c))
+    }
+
+    #[test]
+    fn max_taps_follows_multi_tap_outputs() {
+        let mut binding = ButtonBinding::new(key('a'));
+        assert_eq!(binding.max_taps(), 1);
+
+        binding.multi_tap = Some(MultiTap::default());
+        assert_eq!(binding.max_taps(), 1);
+
+        binding.set_extra_output(ExtraOutput::DoubleTap, Some(key('d')));
+        assert_eq!(binding.max_taps(), 2);
+
+        binding.set_extra_output(ExtraOutput::TripleTap, Some(key('t')));
+        assert_eq!(binding.max_taps(), 3);
+
+        // A triple tap can follow a double tap that does nothing.
+        binding.set_extra_output(ExtraOutput::DoubleTap, None);
+        assert_eq!(binding.max_taps(), 3);
+    }
+
+    #[test]
+    fn get_tap_output_by_tap_count() {
+        let mut binding = ButtonBinding::new(key('a'));
+        assert_eq!(binding.get_tap_output(1), Some(&key('a')));
+        assert_eq!(binding.get_tap_output(2), None);
+
+        binding.multi_tap = Some(MultiTap {
+            double_tap: Some(key('d')),
+            triple_tap: None,
+            window_ms: 250,
+        });
+        assert_eq!(binding.get_tap_output(0), None);
+        assert_eq!(binding.get_tap_output(1), Some(&key('a')));
+        assert_eq!(binding.get_tap_output(2), Some(&key('d')));
+        assert_eq!(binding.get_tap_output(3), None);
+        assert_eq!(binding.get_tap_output(4), None);
+    }
+
+    #[test]
+    fn turbo_to_macro_splits_cycle_by_duty_cycle() {
+        let turbo = Turbo {
+            rate: 10.0,
+            duty_cycle: 0.25,
+        };
+        let m = turbo.to_macro(&key('a'));
+        assert_eq!(
+            m.steps,
+            vec![
+                MacroStep::KeyDown(Some(key('a'))),
+                MacroStep::Wait(25),
+                MacroStep::KeyUp(Some(key('a'))),
+                MacroStep::Wait(75),
+            ]
+        );
+        assert!(m.cancel_on_release);
+        assert!(m.repeat_while_held);
+    }
+
+    #[test]
+    fn turbo_to_macro_keeps_waits_at_least_one_ms() {
+        let turbo = Turbo {
+            rate: Turbo::MAX_RATE,
+            duty_cycle: 1.0,
+        };
+        let m = turbo.to_macro(&key('a'));
+        // 30 presses per second make a 33 ms cycle, all of it held down.
+        assert_eq!(m.steps[1], MacroStep::Wait(33));
+        assert_eq!(m.steps[3], MacroStep::Wait(1));
+    }
+
+    #[test]
+    fn can_turbo_only_key_like_outputs() {
+        assert!(ButtonBinding::new(key('a')).can_turbo());
+        assert!(!ButtonBinding::new(MouseButtonOrKey::Text("hi".to_string())).can_turbo());
+        assert!(!ButtonBinding::new(MouseButtonOrKey::Macro(Macro::default())).can_turbo());
+    }
+
+    #[test]
+    fn can_toggle_only_key_like_outputs() {
+        assert!(ButtonBinding::new(key('a')).can_toggle());
+        assert!(ButtonBinding::new(MouseButtonOrKey::MouseButton(Button::Left)).can_toggle());
+        assert!(!ButtonBinding::new(MouseButtonOrKey::Text("hi".to_string())).can_toggle());
+        assert!(!ButtonBinding::new(MouseButtonOrKey::Command(Box::default())).can_toggle());
+    }
+}
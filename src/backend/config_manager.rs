@@ -1,24 +1,38 @@
+use crate::backend::axis_binding::{AxisBinding, AxisHalf};
+use crate::backend::button_binding::ButtonBinding;
+use crate::backend::display_bounds::EdgeBehavior;
+use crate::backend::gamepad_matcher::{ConnectedGamepad, GamepadMatcher};
+use crate::backend::input_source::{InputSource, OutputSlot};
 use crate::backend::joysticks::Joystick;
-use crate::backend::key_utils::{MouseButtonOrKey, get_enigo_key_from_iced_key};
+use crate::backend::key_utils::MouseButtonOrKey;
+use crate::backend::stick_config::{StickConfig, StickMode};
 use anyhow::{Result, anyhow};
 use directories::BaseDirs;
 use enigo::Button as MouseButton;
-use gilrs::{Button, GamepadId, Gilrs};
-use iced::keyboard::Key as IcedKey;
+use gilrs::{Axis, Button, Gamepad, GamepadId, Gilrs};
 use rfd::FileDialog;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::HashMap;
 use std::fs;
+use std::hash::Hash;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct ProfileConfig {
     gamepad_configs: Vec<GamepadConfig>,
+    // Index of the config given to gamepads that no matcher claimed. Unclaimed gamepads are
+    // ignored when this is None.
+    #[serde(default)]
+    fallback_gamepad_config: Option<usize>,
+    #[serde(default)]
+    pub edge_behavior: EdgeBehavior,
 }
 
 impl Default for ProfileConfig {
     fn default() -> Self {
         Self {
             gamepad_configs: vec![GamepadConfig::default()],
+            fallback_gamepad_config: None,
+            edge_behavior: EdgeBehavior::default(),
         }
     }
 }
@@ -36,43 +50,18 @@ impl ProfileConfig {
 
         if let Some(file_path) = file_path_opt {
             let data_string = fs::read_to_string(file_path)?;
-            return Ok(Some(serde_json::from_str(&*data_string)?));
-        }
-        Ok(None)
-    }
-
-    pub fn toggle_axis_all(&mut self, joystick: Joystick) {
-        // TODO: This function is temporary until proper multi-controller support is implemented!
-        if joystick == Joystick::Left {
-            for gc in &mut self.gamepad_configs {
-                gc.use_left_stick_mouse = !gc.use_left_stick_mouse;
+            let mut profile_config: Self = serde_json::from_str(&data_string)?;
+            if profile_config.gamepad_configs.is_empty() {
+                profile_config
+                    .gamepad_configs
+                    .push(GamepadConfig::default());
             }
-        } else if joystick == Joystick::Right {
-            for gc in &mut self.gamepad_configs {
-                gc.use_right_stick_mouse = !gc.use_right_stick_mouse;
+            for gc in &mut profile_config.gamepad_configs {
+                gc.migrate_legacy_stick_mouse();
             }
+            return Ok(Some(profile_config));
         }
-    }
-
-    pub fn insert_key_to_all(&mut self, btn: Button, key: IcedKey) {
-        // TODO: This function is temporary until proper multi-controller support is implemented!
-        for gc in &mut self.gamepad_configs {
-            gc.insert_key(btn.clone(), key.clone());
-        }
-    }
-
-    pub fn insert_mouse_button_to_all(&mut self, btn: Button, mb: MouseButton) {
-        // TODO: This function is temporary until proper multi-controller support is implemented!
-        for gc in &mut self.gamepad_configs {
-            gc.insert_mouse_button(btn.clone(), mb.clone());
-        }
-    }
-
-    pub fn unset_key_to_all(&mut self, btn: Button) {
-        // TODO: This function is temporary until proper multi-controller support is implemented!
-        for gc in &mut self.gamepad_configs {
-            gc.remove_key(btn);
-        }
+        Ok(None)
     }
 
     pub fn save(&self) -> Result<()> {
@@ -92,51 +81,389 @@ impl ProfileConfig {
         Ok(())
     }
 
-    pub fn get_first_gamepad_config(&self) -> &GamepadConfig {
-        // TODO: This function is temporary until proper multi-controller support is implemented!
-        &self.gamepad_configs[0]
+    pub fn gamepad_config_count(&self) -> usize {
+        self.gamepad_configs.len()
+    }
+
+    pub fn get_gamepad_config_mut(&mut self, index: usize) -> Option<&mut GamepadConfig> {
+        self.gamepad_configs.get_mut(index)
+    }
+
+    pub fn add_gamepad_config(&mut self) -> usize {
+        self.gamepad_configs.push(GamepadConfig::default());
+        self.gamepad_configs.len() - 1
+    }
+
+    pub fn duplicate_gamepad_config(&mut self, index: usize) -> Option<usize> {
+        let mut gc = self.gamepad_configs.get(index)?.clone();
+        // Two identical matchers would just fight over the same controller.
+        gc.matcher = GamepadMatcher::Any;
+        self.gamepad_configs.push(gc);
+        Some(self.gamepad_configs.len() - 1)
+    }
+
+    pub fn remove_gamepad_config(&mut self, index: usize) {
+        // A profile always keeps at least one config to edit.
+        if self.gamepad_configs.len() <= 1 || index >= self.gamepad_configs.len() {
+            return;
+        }
+        self.gamepad_configs.remove(index);
+
+        self.fallback_gamepad_config = match self.fallback_gamepad_config {
+            Some(fallback) if fallback == index => None,
+            Some(fallback) if fallback > index => Some(fallback - 1),
+            fallback => fallback,
+        };
+    }
+
+    pub fn is_fallback_gamepad_config(&self, index: usize) -> bool {
+        self.fallback_gamepad_config == Some(index)
     }
 
-    pub fn get_gamepad_config_map(&self, gilrs: &Gilrs) -> HashMap<GamepadId, GamepadConfig> {
+    pub fn set_fallback_gamepad_config(&mut self, index: usize, is_fallback: bool) {
+        if is_fallback {
+            self.fallback_gamepad_config = Some(index);
+        } else if self.is_fallback_gamepad_config(index) {
+            self.fallback_gamepad_config = None;
+        }
+    }
+
+    fn find_unclaimed_config<K>(
+        &self,
+        gamepad: &ConnectedGamepad,
+        priority: u8,
+        assignments: &HashMap<K, usize>,
+    ) -> Option<usize> {
+        self.gamepad_configs
+            .iter()
+            .enumerate()
+            .find(|(i, gc)| {
+                gc.matcher.priority() == priority
+                    && gc.matcher.matches(gamepad)
+                    && !assignments.values().any(|claimed| claimed == i)
+            })
+            .map(|(i, _)| i)
+    }
+
+    pub fn assign_gamepads(&self, gilrs: &Gilrs) -> HashMap<GamepadId, usize> {
         // The reason we can't store this HashMap directly is that GamepadId is not static between runs.
-        let mut gamepad_config_map: HashMap<GamepadId, GamepadConfig> = HashMap::new();
+        let gamepads: Vec<(GamepadId, ConnectedGamepad)> = gilrs
+            .gamepads()
+            .map(|(gamepad_id, gamepad)| (gamepad_id, ConnectedGamepad::new(&gamepad)))
+            .collect();
+        self.assign_connected_gamepads(&gamepads)
+    }
+
+    // Generic over the ID so the rules can be tested without gilrs.
+    fn assign_connected_gamepads<K: Copy + Eq + Hash>(
+        &self,
+        gamepads: &[(K, ConnectedGamepad)],
+    ) -> HashMap<K, usize> {
+        let mut assignments: HashMap<K, usize> = HashMap::new();
 
-        // TODO: Need to come up with a better way to assign gamepads to configs!
-        let mut connected_gamepad_iter = gilrs.gamepads();
-        for gc in &self.gamepad_configs {
-            if let Some((gamepad_id, _)) = connected_gamepad_iter.next() {
-                gamepad_config_map.insert(gamepad_id, gc.clone());
+        // Every matcher gets to claim a gamepad before any less specific matcher does, so e.g. an
+        // "Any" config can't steal a controller that has a config of its own.
+        for priority in 0..=GamepadMatcher::Any.priority() {
+            for (gamepad_id, gamepad) in gamepads {
+                if assignments.contains_key(gamepad_id) {
+                    continue;
+                }
+                if let Some(i) = self.find_unclaimed_config(gamepad, priority, &assignments) {
+                    assignments.insert(*gamepad_id, i);
+                }
             }
         }
 
-        gamepad_config_map
+        if let Some(fallback) = self.get_fallback_gamepad_config_index() {
+            for (gamepad_id, _) in gamepads {
+                assignments.entry(*gamepad_id).or_insert(fallback);
+            }
+        }
+
+        assignments
+    }
+
+    pub fn assign_gamepad(
+        &self,
+        gamepad: &Gamepad,
+        assignments: &HashMap<GamepadId, usize>,
+    ) -> Option<usize> {
+        let gamepad = ConnectedGamepad::new(gamepad);
+        (0..=GamepadMatcher::Any.priority())
+            .find_map(|priority| self.find_unclaimed_config(&gamepad, priority, assignments))
+            .or(self.get_fallback_gamepad_config_index())
+    }
+
+    pub fn get_gamepad_config(&self, index: usize) -> Option<&GamepadConfig> {
+        self.gamepad_configs.get(index)
+    }
+
+    fn get_fallback_gamepad_config_index(&self) -> Option<usize> {
+        self.fallback_gamepad_config
+            .filter(|&fallback| fallback < self.gamepad_configs.len())
     }
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct GamepadConfig {
-    button_map: HashMap<Button, MouseButtonOrKey>,
-    pub use_left_stick_mouse: bool,
-    pub use_right_stick_mouse: bool,
+    #[serde(default)]
+    pub matcher: GamepadMatcher,
+    #[serde(deserialize_with = "deserialize_button_map")]
+    button_map: HashMap<Button, ButtonBinding>,
+    // Only read from profiles saved before sticks had a mode, see migrate_legacy_stick_mouse.
+    #[serde(default, skip_serializing)]
+    use_left_stick_mouse: bool,
+    #[serde(default, skip_serializing)]
+    use_right_stick_mouse: bool,
+    #[serde(default)]
+    left_stick: StickConfig,
+    #[serde(default)]
+    right_stick: StickConfig,
+    #[serde(default)]
+    axis_bindings: Vec<AxisBinding>,
+}
+
+// Profiles saved before buttons had any options stored just the output for each button.
+fn deserialize_button_map<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<HashMap<Button, ButtonBinding>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StoredButtonBinding {
+        Binding(ButtonBinding),
+        Legacy(MouseButtonOrKey),
+    }
+
+    let stored = HashMap::<Button, StoredButtonBinding>::deserialize(deserializer)?;
+    Ok(stored
+        .into_iter()
+        .map(|(btn, binding)| match binding {
+            StoredButtonBinding::Binding(binding) => (btn, binding),
+            StoredButtonBinding::Legacy(output) => (btn, ButtonBinding::new(output)),
+        })
+        .collect())
 }
 
 impl GamepadConfig {
-    pub fn insert_key(&mut self, btn: Button, key: IcedKey) {
-        if let Some(k) = get_enigo_key_from_iced_key(key) {
-            self.button_map.insert(btn, MouseButtonOrKey::Key(k));
+    fn migrate_legacy_stick_mouse(&mut self) {
+        if std::mem::take(&mut self.use_left_stick_mouse) {
+            self.left_stick.mode = StickMode::Cursor;
+        }
+        if std::mem::take(&mut self.use_right_stick_mouse) {
+            self.right_stick.mode = StickMode::Cursor;
+        }
+    }
+
+    pub fn get_stick_config(&self, joystick: &Joystick) -> &StickConfig {
+        match joystick {
+            Joystick::Left => &self.left_stick,
+            Joystick::Right => &self.right_stick,
+        }
+    }
+
+    pub fn set_stick_config(&mut self, joystick: Joystick, stick_config: StickConfig) {
+        match joystick {
+            Joystick::Left => self.left_stick = stick_config,
+            Joystick::Right => self.right_stick = stick_config,
         }
     }
 
-    pub fn insert_mouse_button(&mut self, btn: Button, mb: MouseButton) {
-        self.button_map
-            .insert(btn, MouseButtonOrKey::MouseButton(mb));
+    pub fn get_axis_bindings(&self) -> &[AxisBinding] {
+        &self.axis_bindings
+    }
+
+    fn get_axis_binding(&self, axis: Axis, half: AxisHalf) -> Option<&AxisBinding> {
+        self.axis_bindings
+            .iter()
+            .find(|ab| ab.axis == axis && ab.half == half)
+    }
+
+    fn get_axis_binding_mut(&mut self, axis: Axis, half: AxisHalf) -> &mut AxisBinding {
+        let i = match self
+            .axis_bindings
+            .iter()
+            .position(|ab| ab.axis == axis && ab.half == half)
+        {
+            Some(i) => i,
+            None => {
+                self.axis_bindings.push(AxisBinding::new(axis, half));
+                self.axis_bindings.len() - 1
+            }
+        };
+        &mut self.axis_bindings[i]
     }
 
-    pub fn remove_key(&mut self, btn: Button) {
-        self.button_map.remove(&btn);
+    pub fn add_axis_binding(&mut self, axis: Axis, half: AxisHalf) {
+        self.get_axis_binding_mut(axis, half);
     }
 
-    pub fn get_key(&self, btn: &Button) -> Option<&MouseButtonOrKey> {
+    pub fn set_axis_binding(&mut self, binding: AxisBinding) {
+        let (axis, half) = (binding.axis, binding.half);
+        *self.get_axis_binding_mut(axis, half) = binding;
+    }
+
+    pub fn remove_axis_binding(&mut self, axis: Axis, half: AxisHalf) {
+        self.axis_bindings
+            .retain(|ab| ab.axis != axis || ab.half != half);
+    }
+
+    pub fn get_button_binding(&self, btn: &Button) -> Option<&ButtonBinding> {
         self.button_map.get(btn)
     }
+
+    pub fn set_button_binding(&mut self, btn: Button, binding: ButtonBinding) {
+        self.button_map.insert(btn, binding);
+    }
+
+    // Every direction of a stick shares the stick's config, so any change to it counts for all of
+    // them.
+    pub fn has_same_binding(&self, other: &GamepadConfig, source: &InputSource) -> bool {
+        match source {
+            InputSource::Button(btn) => {
+                self.get_button_binding(btn) == other.get_button_binding(btn)
+            }
+            InputSource::StickDirection(joystick, _) => {
+                self.get_stick_config(joystick) == other.get_stick_config(joystick)
+            }
+            InputSource::Axis(axis, half) => {
+                self.get_axis_binding(*axis, *half) == other.get_axis_binding(*axis, *half)
+            }
+        }
+    }
+
+    pub fn insert_output(&mut self, slot: OutputSlot, output: MouseButtonOrKey) {
+        self.set_output(slot, Some(output));
+    }
+
+    pub fn insert_mouse_button(&mut self, slot: OutputSlot, mb: MouseButton) {
+        self.set_output(slot, Some(MouseButtonOrKey::MouseButton(mb)));
+    }
+
+    pub fn remove_key(&mut self, slot: OutputSlot) {
+        self.set_output(slot, None);
+    }
+
+    pub fn get_key(&self, slot: &OutputSlot) -> Option<&MouseButtonOrKey> {
+        let source = match slot {
+            OutputSlot::Input(source) => source,
+            OutputSlot::Extra(btn, extra) => {
+                return self.button_map.get(btn)?.get_extra_output(*extra);
+            }
+        };
+        match source {
+            InputSource::Button(btn) => self.button_map.get(btn).map(|binding| &binding.output),
+            InputSource::StickDirection(joystick, direction) => self
+                .get_stick_config(joystick)
+                .direction_keys
+                .get(*direction),
+            InputSource::Axis(axis, half) => self
+                .get_axis_binding(*axis, *half)
+                .and_then(|ab| ab.output.as_ref()),
+        }
+    }
+
+    fn set_output(&mut self, slot: OutputSlot, output: Option<MouseButtonOrKey>) {
+        let source = match slot {
+            OutputSlot::Input(source) => source,
+            OutputSlot::Extra(btn, extra) => {
+                if let Some(binding) = self.button_map.get_mut(&btn) {
+                    binding.set_extra_output(extra, output);
+                }
+                return;
+            }
+        };
+        match (source, output) {
+            (InputSource::Button(btn), Some(output)) => {
+                // A different output keeps the options that were set for the button.
+                match self.button_map.get_mut(&btn) {
+                    Some(binding) => binding.output = output,
+                    None => {
+                        self.button_map.insert(btn, ButtonBinding::new(output));
+                    }
+                }
+            }
+            (InputSource::Button(btn), None) => {
+                self.button_map.remove(&btn);
+            }
+            (InputSource::StickDirection(joystick, direction), output) => {
+                let stick_config = match joystick {
+                    Joystick::Left => &mut self.left_stick,
+                    Joystick::Right => &mut self.right_stick,
+                };
+                stick_config.direction_keys.set(direction, output);
+            }
+            (InputSource::Axis(axis, half), output) => {
+                self.get_axis_binding_mut(axis, half).output = output;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gamepad(name: &str, uuid: &str) -> ConnectedGamepad {
+        ConnectedGamepad {
+            name: name.to_string(),
+            uuid: uuid.to_string(),
+        }
+    }
+
+    fn profile_with_matchers(matchers: Vec<GamepadMatcher>) -> ProfileConfig {
+        ProfileConfig {
+            gamepad_configs: matchers
+                .into_iter()
+                .map(|matcher| GamepadConfig {
+                    matcher,
+                    ..GamepadConfig::default()
+                })
+                .collect(),
+            ..ProfileConfig::default()
+        }
+    }
+
+    #[test]
+    fn assign_gamepads_by_priority() {
+        // Listed from least to most specific, so the order of the configs can't decide it.
+        let profile_config = profile_with_matchers(vec![
+            GamepadMatcher::Any,
+            GamepadMatcher::Name("Pad".to_string()),
+            GamepadMatcher::Uuid("01".to_string()),
+        ]);
+        // The gamepads are listed so that the most specific one comes last too.
+        let gamepads = [
+            (0, gamepad("Other", "03")),
+            (1, gamepad("Pad", "02")),
+            (2, gamepad("Pad", "01")),
+            (3, gamepad("Pad", "04")),
+        ];
+
+        let assignments = profile_config.assign_connected_gamepads(&gamepads);
+        assert_eq!(assignments.get(&2), Some(&2));
+        assert_eq!(assignments.get(&1), Some(&1));
+        assert_eq!(assignments.get(&0), Some(&0));
+        // Every config has claimed a gamepad already and there's no fallback.
+        assert_eq!(assignments.get(&3), None);
+    }
+
+    #[test]
+    fn assign_gamepads_falls_back_for_unclaimed() {
+        let mut profile_config = profile_with_matchers(vec![
+            GamepadMatcher::Name("Pad".to_string()),
+            GamepadMatcher::Uuid("01".to_string()),
+        ]);
+        profile_config.set_fallback_gamepad_config(0, true);
+        let gamepads = [
+            (0, gamepad("Pad", "02")),
+            (1, gamepad("Pad", "03")),
+            (2, gamepad("Other", "04")),
+        ];
+
+        let assignments = profile_config.assign_connected_gamepads(&gamepads);
+        assert_eq!(assignments.get(&0), Some(&0));
+        // The fallback config can be given to any number of gamepads.
+        assert_eq!(assignments.get(&1), Some(&0));
+        assert_eq!(assignments.get(&2), Some(&0));
+    }
 }
@@ -1,99 +1,989 @@
-use crate::backend::config_manager::{GamepadConfig, ProfileConfig};
+use crate::backend::button_binding::{ButtonBinding, ExtraOutput};
+use crate::backend::config_manager::ProfileConfig;
+use crate::backend::display_bounds::{DisplayBounds, DisplayBoundsWatcher, EdgeBehavior};
+use crate::backend::held_outputs::HeldOutputs;
+use crate::backend::input_source::InputSource;
+use crate::backend::joysticks::{Joystick, StickDirection};
 use crate::backend::key_utils::MouseButtonOrKey;
-use crate::utils::lock_error_handler_string;
+use crate::backend::stick_config::{ScrollStyle, StickConfig, StickMode};
 use anyhow::Result;
-use enigo::{Coordinate, Direction, Enigo, Keyboard, Mouse, Settings};
-use gilrs::EventType::{AxisChanged, ButtonPressed, ButtonReleased};
-use gilrs::{Axis, Button, Event, Gilrs};
+use enigo::{Axis, Coordinate, Enigo, Mouse, Settings};
+use gilrs::EventType::{AxisChanged, ButtonPressed, ButtonReleased, Connected, Disconnected};
+use gilrs::{Button, Event, GamepadId, Gilrs};
+use iced::futures::channel::mpsc::UnboundedSender;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::hash::Hash;
+use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, Mutex};
+use std::sync::mpsc::Receiver;
+use std::time::{Duration, Instant};
 
-const DEADZONE: f32 = 0.05;
-const MOUSE_SPEED_MODIFIER: f32 = 0.5;
+// How far the cursor travels per second with the stick fully tilted.
+const MOUSE_SPEED_PIXELS_PER_SECOND: f32 = 1000.0;
+// Continuous outputs (e.g. the mouse) are updated at this fixed rate rather than whenever events
+// happen to arrive, which also bounds how long the handler can take to notice it was stopped.
+const TICK_INTERVAL: Duration = Duration::from_millis(8);
+// Keeps the cursor from jumping across the screen if a tick was delayed, e.g. by a suspend.
+const MAX_TICK_DELTA: Duration = Duration::from_millis(50);
+// Monitors can be plugged in or rearranged while the handler runs, so their layout is re-read this often.
+const DISPLAY_BOUNDS_REFRESH_INTERVAL: Duration = Duration::from_secs(5);
 
-fn perform_key_press(
-    enigo: &mut Enigo,
-    agc: &GamepadConfig,
-    btn: Button,
-    dir: Direction,
-) -> Result<(), String> {
-    if let Some(mb_key) = agc.get_key(&btn) {
-        if let MouseButtonOrKey::MouseButton(mb) = mb_key {
-            enigo.button(*mb, dir).map_err(|e| e.to_string())?;
-        } else if let MouseButtonOrKey::Key(key) = mb_key {
-            enigo.key(*key, dir).map_err(|e| e.to_string())?;
+#[derive(Clone, Debug)]
+pub enum HandlerStatus {
+    Started,
+    ControllerConnected(String),
+    ControllerDisconnected(String),
+    ProfileApplied,
+    Error(String),
+    CommandFailed(String),
+    // Every toggle button that's currently held, as the index of its gamepad config and the button.
+    LatchedButtonsChanged(Vec<(usize, Button)>),
+    Stopped,
+}
+
+impl Display for HandlerStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HandlerStatus::Started => write!(f, "Started"),
+            HandlerStatus::ControllerConnected(name) => write!(f, "{} connected", name),
+            HandlerStatus::ControllerDisconnected(name) => write!(f, "{} disconnected", name),
+            HandlerStatus::ProfileApplied => write!(f, "Profile applied"),
+            HandlerStatus::Error(e) => write!(f, "Error: {}", e),
+            HandlerStatus::CommandFailed(e) => write!(f, "Command failed: {}", e),
+            HandlerStatus::LatchedButtonsChanged(latched) => {
+                write!(f, "{} toggle button(s) held", latched.len())
+            }
+            HandlerStatus::Stopped => write!(f, "Stopped"),
         }
     }
-    Ok(())
 }
 
-pub async fn handle_controller_input(
-    profile_config: Arc<Mutex<ProfileConfig>>,
-    is_handler_running: Arc<AtomicBool>,
-) -> Result<(), String> {
-    let mut enigo = Enigo::new(&Settings::default()).map_err(|e| e.to_string())?;
-    let mut gilrs = Gilrs::new().map_err(|e| e.to_string())?;
-    let active_gamepad_config_map = profile_config
-        .lock()
-        .map_err(lock_error_handler_string)?
-        .get_gamepad_config_map(&gilrs);
-
-    let (mouse_x_pix, mouse_y_pix) = enigo.location().unwrap_or((0, 0));
-    let mut mouse_x_pos = mouse_x_pix as f32;
-    let mut mouse_y_pos = mouse_y_pix as f32;
-    let mut mouse_x_amt = 0.0;
-    let mut mouse_y_amt = 0.0;
-
-    while is_handler_running.load(Ordering::Relaxed) == true {
-        // Examine new events
-        while let Some(Event { id, event, .. }) = gilrs.next_event() {
-            let agc = active_gamepad_config_map
-                .get(&id)
-                .ok_or("ERROR: Gamepad config couldn't be mapped to a Gamepad!")?;
-
-            match event {
-                ButtonPressed(btn, _) => {
-                    perform_key_press(&mut enigo, agc, btn, Direction::Press)?;
-                }
-                ButtonReleased(btn, _) => {
-                    perform_key_press(&mut enigo, agc, btn, Direction::Release)?;
-                }
-                AxisChanged(axis, amt, _) => {
-                    if (agc.use_left_stick_mouse && axis == Axis::LeftStickX)
-                        || (agc.use_right_stick_mouse && axis == Axis::RightStickX)
-                    {
-                        mouse_x_amt = if amt.abs() > DEADZONE {
-                            amt * MOUSE_SPEED_MODIFIER
-                        } else {
-                            0.0
-                        };
-                    } else if (agc.use_left_stick_mouse && axis == Axis::LeftStickY)
-                        || (agc.use_right_stick_mouse && axis == Axis::RightStickY)
-                    {
-                        mouse_y_amt = if amt.abs() > DEADZONE {
-                            -amt * MOUSE_SPEED_MODIFIER
-                        } else {
-                            0.0
-                        };
+#[derive(Default)]
+struct StickState {
+    full_tilt_time: Duration,
+    scroll_x: ScrollState,
+    scroll_y: ScrollState,
+    held_directions: Vec<StickDirection>,
+}
+
+#[derive(Default)]
+struct ScrollState {
+    last_amt: f32,
+    // Fraction of a notch that wasn't scrolled yet, carried over to the next tick.
+    remainder: f32,
+}
+
+impl ScrollState {
+    // Returns how many whole notches to scroll this tick, where notches is how many a fully tilted
+    // stick would have scrolled since the last one.
+    fn take_notches(&mut self, amt: f32, style: ScrollStyle, notches: f32) -> i32 {
+        let last_amt = std::mem::replace(&mut self.last_amt, amt);
+        if amt == 0.0 {
+            self.remainder = 0.0;
+            return 0;
+        }
+
+        match style {
+            ScrollStyle::Smooth => self.remainder += amt * notches,
+            ScrollStyle::Notches => {
+                if last_amt == 0.0 || last_amt.signum() != amt.signum() {
+                    // A short flick should still scroll exactly one notch.
+                    self.remainder = amt.signum();
+                } else {
+                    self.remainder += amt.signum() * notches;
+                }
+            }
+        }
+
+        let whole_notches = self.remainder.trunc();
+        self.remainder -= whole_notches;
+        whole_notches as i32
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum PendingOutcome {
+    Tapped(usize),
+    Held,
+}
+
+// A button with a hold or multi-tap action, while it isn't known yet which of its outputs it's
+// going to trigger.
+struct PendingButton {
+    binding: ButtonBinding,
+    is_down: bool,
+    changed_at: Instant,
+    taps: usize,
+}
+
+impl PendingButton {
+    fn new(binding: ButtonBinding, now: Instant) -> Self {
+        Self {
+            binding,
+            is_down: true,
+            changed_at: now,
+            taps: 0,
+        }
+    }
+
+    fn press(&mut self, now: Instant) {
+        self.is_down = true;
+        self.changed_at = now;
+    }
+
+    fn release(&mut self, now: Instant) -> Option<PendingOutcome> {
+        // The threshold can pass between two ticks, which makes this the end of a short hold
+        // rather than a tap.
+        let is_held = self.is_held(now);
+        self.is_down = false;
+        self.changed_at = now;
+        if is_held {
+            return Some(PendingOutcome::Held);
+        }
+        self.taps += 1;
+        self.resolve(now)
+    }
+
+    fn resolve(&self, now: Instant) -> Option<PendingOutcome> {
+        if self.is_held(now) {
+            Some(PendingOutcome::Held)
+        } else if self.is_tap_finished(now) {
+            Some(PendingOutcome::Tapped(self.taps))
+        } else {
+            None
+        }
+    }
+
+    fn get_output(&self, outcome: PendingOutcome) -> Option<&MouseButtonOrKey> {
+        match outcome {
+            PendingOutcome::Tapped(taps) => self.binding.get_tap_output(taps),
+            PendingOutcome::Held => self.binding.get_extra_output(ExtraOutput::Hold),
+        }
+    }
+
+    // Only the first press can turn into a hold, later ones finish a multi-tap.
+    fn is_held(&self, now: Instant) -> bool {
+        self.is_down
+            && self.taps == 0
+            && self.binding.hold.as_ref().is_some_and(|hold| {
+                now.saturating_duration_since(self.changed_at) >= hold.threshold()
+            })
+    }
+
+    fn is_tap_finished(&self, now: Instant) -> bool {
+        !self.is_down
+            && (self.taps >= self.binding.max_taps()
+                || self.binding.multi_tap.as_ref().is_some_and(|multi_tap| {
+                    now.saturating_duration_since(self.changed_at) >= multi_tap.window()
+                }))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct SettledButton {
+    output: MouseButtonOrKey,
+    // A button that's still down holds its output until it's released, like any other output.
+    is_down: bool,
+}
+
+// Generic over the key so it can be tested without gamepads.
+struct PendingButtons<K> {
+    buttons: HashMap<K, PendingButton>,
+    settled: Vec<(K, SettledButton)>,
+}
+
+impl<K: Copy + Eq + Hash> PendingButtons<K> {
+    fn new() -> Self {
+        Self {
+            buttons: HashMap::new(),
+            settled: Vec::new(),
+        }
+    }
+
+    fn insert(&mut self, key: K, binding: ButtonBinding, now: Instant) {
+        self.buttons.insert(key, PendingButton::new(binding, now));
+    }
+
+    // Returns whether the button is pending, in which case the press is its next tap.
+    fn press(&mut self, key: &K, now: Instant) -> bool {
+        let Some(pending) = self.buttons.get_mut(key) else {
+            return false;
+        };
+        pending.press(now);
+        true
+    }
+
+    // Returns whether the button is pending, in which case the release is part of its tap or hold.
+    fn release(&mut self, key: &K, now: Instant) -> bool {
+        let Some(pending) = self.buttons.get_mut(key) else {
+            return false;
+        };
+        if let Some(outcome) = pending.release(now) {
+            self.settle(key, outcome);
+        }
+        true
+    }
+
+    fn retain(&mut self, mut predicate: impl FnMut(&K) -> bool) {
+        self.buttons.retain(|key, _| predicate(key));
+    }
+
+    // Settles every pending button that was held past its threshold or whose taps have ended, and
+    // hands back everything settled since the last call.
+    fn take_settled(&mut self, now: Instant) -> Vec<(K, SettledButton)> {
+        let resolved: Vec<(K, PendingOutcome)> = self
+            .buttons
+            .iter()
+            .filter_map(|(&key, pending)| Some((key, pending.resolve(now)?)))
+            .collect();
+        for (key, outcome) in resolved {
+            self.settle(&key, outcome);
+        }
+        std::mem::take(&mut self.settled)
+    }
+
+    fn settle(&mut self, key: &K, outcome: PendingOutcome) {
+        let Some(pending) = self.buttons.remove(key) else {
+            return;
+        };
+        if let Some(output) = pending.get_output(outcome) {
+            let settled = SettledButton {
+                output: output.clone(),
+                is_down: pending.is_down,
+            };
+            self.settled.push((*key, settled));
+        }
+    }
+}
+
+struct ControllerHandler {
+    enigo: Enigo,
+    gilrs: Gilrs,
+    status_sender: UnboundedSender<HandlerStatus>,
+    profile_config: ProfileConfig,
+    assignments: HashMap<GamepadId, usize>,
+    held_outputs: HeldOutputs,
+    stick_states: HashMap<(GamepadId, Joystick), StickState>,
+    // Axis bindings that are past their press threshold, whether or not they have an output.
+    pressed_axes: HashSet<(GamepadId, InputSource)>,
+    latched_buttons: HashSet<(GamepadId, Button)>,
+    pending_buttons: PendingButtons<(GamepadId, Button)>,
+    display_bounds: DisplayBounds,
+    display_bounds_watcher: DisplayBoundsWatcher,
+    mouse_x_pos: f32,
+    mouse_y_pos: f32,
+    // Fractions of a pixel that relative movement couldn't send yet, carried over to the next tick.
+    mouse_x_remainder: f32,
+    mouse_y_remainder: f32,
+}
+
+impl ControllerHandler {
+    fn new(
+        profile_config: ProfileConfig,
+        status_sender: UnboundedSender<HandlerStatus>,
+    ) -> Result<Self, String> {
+        let enigo = Enigo::new(&Settings::default()).map_err(|e| e.to_string())?;
+        let gilrs = Gilrs::new().map_err(|e| e.to_string())?;
+        let assignments = profile_config.assign_gamepads(&gilrs);
+        let display_bounds = DisplayBounds::detect(&enigo);
+        let (mouse_x_pix, mouse_y_pix) = enigo.location().unwrap_or((0, 0));
+
+        Ok(Self {
+            enigo,
+            gilrs,
+            status_sender,
+            profile_config,
+            assignments,
+            held_outputs: HeldOutputs::default(),
+            stick_states: HashMap::new(),
+            pressed_axes: HashSet::new(),
+            latched_buttons: HashSet::new(),
+            pending_buttons: PendingButtons::new(),
+            display_bounds,
+            display_bounds_watcher: DisplayBoundsWatcher::start(DISPLAY_BOUNDS_REFRESH_INTERVAL),
+            mouse_x_pos: mouse_x_pix as f32,
+            mouse_y_pos: mouse_y_pix as f32,
+            mouse_x_remainder: 0.0,
+            mouse_y_remainder: 0.0,
+        })
+    }
+
+    fn run(
+        &mut self,
+        profile_updates: &Receiver<ProfileConfig>,
+        is_handler_running: &AtomicBool,
+    ) -> Result<(), String> {
+        let mut last_tick = Instant::now();
+        let mut next_tick = last_tick + TICK_INTERVAL;
+
+        while is_handler_running.load(Ordering::Relaxed) {
+            // Only the most recent profile matters if several edits were made since the last iteration.
+            if let Some(updated_profile_config) = profile_updates.try_iter().last() {
+                self.apply_profile(updated_profile_config)?;
+            }
+
+            let now = Instant::now();
+            if now >= next_tick {
+                self.tick((now - last_tick).min(MAX_TICK_DELTA))?;
+                last_tick = now;
+                // Skip ticks that were missed instead of firing them all at once to catch up.
+                next_tick = (next_tick + TICK_INTERVAL).max(now);
+            }
+
+            // Sleep until either an event arrives or the next tick is due.
+            let timeout = next_tick.saturating_duration_since(Instant::now());
+            if let Some(event) = self.gilrs.next_event_blocking(Some(timeout)) {
+                self.handle_event(event)?;
+                while let Some(event) = self.gilrs.next_event() {
+                    self.handle_event(event)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn send_status(&self, status: HandlerStatus) {
+        // This only fails once the UI has gone away, at which point nobody is listening anyway.
+        let _ = self.status_sender.unbounded_send(status);
+    }
+
+    fn send_latched_buttons(&self) {
+        let latched = self
+            .latched_buttons
+            .iter()
+            .filter_map(|(id, btn)| Some((*self.assignments.get(id)?, *btn)))
+            .collect();
+        self.send_status(HandlerStatus::LatchedButtonsChanged(latched));
+    }
+
+    // Only inputs whose binding changed are reset, so that e.g. dragging a slider in the UI doesn't
+    // release every held key on each step.
+    fn apply_profile(&mut self, profile_config: ProfileConfig) -> Result<(), String> {
+        let old_assignments = std::mem::replace(
+            &mut self.assignments,
+            profile_config.assign_gamepads(&self.gilrs),
+        );
+        let old_profile_config = std::mem::replace(&mut self.profile_config, profile_config);
+        let is_changed = |id: &GamepadId, source: &InputSource| {
+            let old = old_assignments
+                .get(id)
+                .and_then(|&i| old_profile_config.get_gamepad_config(i));
+            let new = self
+                .assignments
+                .get(id)
+                .and_then(|&i| self.profile_config.get_gamepad_config(i));
+            match (old, new) {
+                (Some(old), Some(new)) => !old.has_same_binding(new, source),
+                (old, new) => old.is_some() != new.is_some(),
+            }
+        };
+
+        let result = self
+            .held_outputs
+            .release_where(&mut self.enigo, |(id, source)| is_changed(id, source));
+        self.stick_states.retain(|(id, joystick), _| {
+            !is_changed(
+                id,
+                &InputSource::StickDirection(*joystick, StickDirection::Up),
+            )
+        });
+        self.pressed_axes
+            .retain(|(id, source)| !is_changed(id, source));
+        self.pending_buttons
+            .retain(|(id, btn)| !is_changed(id, &InputSource::Button(*btn)));
+        let had_latched_buttons = !self.latched_buttons.is_empty();
+        self.latched_buttons
+            .retain(|(id, btn)| !is_changed(id, &InputSource::Button(*btn)));
+        // Sent even if no toggle was dropped, since the remaining ones may belong to a config at a
+        // different index now.
+        if had_latched_buttons {
+            self.send_latched_buttons();
+        }
+        result?;
+        self.send_status(HandlerStatus::ProfileApplied);
+        Ok(())
+    }
+
+    fn handle_event(&mut self, Event { id, event, .. }: Event) -> Result<(), String> {
+        match event {
+            Connected => {
+                let name = self.gilrs.gamepad(id).name().to_string();
+                self.send_status(HandlerStatus::ControllerConnected(name));
+                if !self.assignments.contains_key(&id)
+                    && let Some(i) = self
+                        .profile_config
+                        .assign_gamepad(&self.gilrs.gamepad(id), &self.assignments)
+                {
+                    self.assignments.insert(id, i);
+                }
+                return Ok(());
+            }
+            Disconnected => {
+                let name = self.gilrs.gamepad(id).name().to_string();
+                self.send_status(HandlerStatus::ControllerDisconnected(name));
+                self.assignments.remove(&id);
+                self.stick_states.retain(|(stick_id, _), _| *stick_id != id);
+                self.pressed_axes.retain(|(axis_id, _)| *axis_id != id);
+                self.pending_buttons
+                    .retain(|(pending_id, _)| *pending_id != id);
+                if self
+                    .latched_buttons
+                    .iter()
+                    .any(|(latched_id, _)| *latched_id == id)
+                {
+                    self.latched_buttons
+                        .retain(|(latched_id, _)| *latched_id != id);
+                    self.send_latched_buttons();
+                }
+                // The other gamepads keep being mapped even if something of this one couldn't be
+                // released.
+                if let Err(e) = self.held_outputs.release_gamepad(&mut self.enigo, id) {
+                    self.send_status(HandlerStatus::Error(e));
+                }
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        // Gamepads that no config was assigned to are simply ignored. The fields are borrowed
+        // directly here so that self.enigo and self.held_outputs stay free to be mutated.
+        let Some(agc) = self
+            .assignments
+            .get(&id)
+            .and_then(|&i| self.profile_config.get_gamepad_config(i))
+        else {
+            return Ok(());
+        };
+
+        match event {
+            ButtonPressed(btn, _) => {
+                let source = InputSource::Button(btn);
+                if self.pending_buttons.press(&(id, btn), Instant::now()) {
+                    // The next tap of a multi-tap.
+                    return Ok(());
+                }
+                if self.latched_buttons.remove(&(id, btn)) {
+                    self.held_outputs.release(&mut self.enigo, id, source)?;
+                    self.send_latched_buttons();
+                } else if let Some(binding) = agc.get_button_binding(&btn) {
+                    if binding.is_delayed() {
+                        // The output is decided once it's known whether the button is tapped, held
+                        // or tapped again.
+                        self.pending_buttons
+                            .insert((id, btn), binding.clone(), Instant::now());
+                        return Ok(());
+                    }
+                    self.held_outputs.press(
+                        &mut self.enigo,
+                        id,
+                        source,
+                        &binding.get_press_output(),
+                    )?;
+                    if binding.toggle && binding.can_toggle() {
+                        self.latched_buttons.insert((id, btn));
+                        self.send_latched_buttons();
                     }
                 }
-                _ => {}
+            }
+            ButtonReleased(btn, _) => {
+                let source = InputSource::Button(btn);
+                if self.pending_buttons.release(&(id, btn), Instant::now()) {
+                    self.trigger_settled_buttons()?;
+                } else if !self.latched_buttons.contains(&(id, btn)) {
+                    // A latched button is released by its next press instead.
+                    self.held_outputs.release(&mut self.enigo, id, source)?;
+                }
+            }
+            AxisChanged(axis, value, _) => {
+                for ab in agc.get_axis_bindings().iter().filter(|ab| ab.axis == axis) {
+                    let source = InputSource::Axis(axis, ab.half);
+                    let was_pressed = self.pressed_axes.contains(&(id, source));
+                    let is_pressed = ab.is_pressed(value, was_pressed);
+                    if is_pressed && !was_pressed {
+                        self.pressed_axes.insert((id, source));
+                        if let Some(output) = &ab.output {
+                            self.held_outputs
+                                .press(&mut self.enigo, id, source, output)?;
+                        }
+                    } else if !is_pressed && was_pressed {
+                        self.pressed_axes.remove(&(id, source));
+                        self.held_outputs.release(&mut self.enigo, id, source)?;
+                    }
+                }
+            }
+            _ => {}
+        };
+        Ok(())
+    }
+
+    fn tick(&mut self, elapsed: Duration) -> Result<(), String> {
+        if let Some(display_bounds) = self.display_bounds_watcher.take_updated() {
+            self.display_bounds = display_bounds;
+        }
+        self.held_outputs.tick(&mut self.enigo)?;
+        for e in self.held_outputs.take_command_errors() {
+            self.send_status(HandlerStatus::CommandFailed(e));
+        }
+        self.trigger_settled_buttons()?;
+        self.move_mouse(elapsed)?;
+        self.scroll(elapsed)?;
+        self.press_stick_keys()
+    }
+
+    fn trigger_settled_buttons(&mut self) -> Result<(), String> {
+        for ((id, btn), settled) in self.pending_buttons.take_settled(Instant::now()) {
+            let source = InputSource::Button(btn);
+            if settled.is_down {
+                self.held_outputs
+                    .press(&mut self.enigo, id, source, &settled.output)?;
+            } else {
+                self.held_outputs
+                    .tap(&mut self.enigo, id, source, &settled.output)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Adds up every stick that currently drives the mouse in the given movement mode, so they never
+    // fight over the cursor.
+    fn get_stick_mouse_amount(&mut self, elapsed: Duration, relative: bool) -> (f32, f32) {
+        let mut mouse_x_amt = 0.0;
+        let mut mouse_y_amt = 0.0;
+
+        for (&id, &i) in &self.assignments {
+            let (Some(agc), Some(gamepad)) = (
+                self.profile_config.get_gamepad_config(i),
+                self.gilrs.connected_gamepad(id),
+            ) else {
+                continue;
             };
+
+            for joystick in [Joystick::Left, Joystick::Right] {
+                let sc = agc.get_stick_config(&joystick);
+                if sc.mode != StickMode::Cursor || sc.relative_mouse != relative {
+                    continue;
+                }
+                let (x_axis, y_axis) = joystick.axes();
+                let (x_amt, y_amt) = sc.apply(gamepad.value(x_axis), gamepad.value(y_axis));
+                let (x_amt, y_amt) = sc.apply_response_curve(x_amt, y_amt);
+
+                let stick_state = self.stick_states.entry((id, joystick)).or_default();
+                if x_amt.hypot(y_amt) >= StickConfig::FULL_TILT {
+                    stick_state.full_tilt_time += elapsed;
+                } else {
+                    stick_state.full_tilt_time = Duration::ZERO;
+                }
+                let acceleration =
+                    sc.get_acceleration_multiplier(stick_state.full_tilt_time.as_secs_f32());
+
+                mouse_x_amt += x_amt * sc.sensitivity_x * acceleration;
+                // The stick's Y axis points up while the screen's points down.
+                mouse_y_amt -= y_amt * sc.sensitivity_y * acceleration;
+            }
         }
 
+        (mouse_x_amt, mouse_y_amt)
+    }
+
+    fn move_mouse(&mut self, elapsed: Duration) -> Result<(), String> {
+        let pixels = MOUSE_SPEED_PIXELS_PER_SECOND * elapsed.as_secs_f32();
+
+        let (rel_x_amt, rel_y_amt) = self.get_stick_mouse_amount(elapsed, true);
+        if rel_x_amt.abs() > 0.0 || rel_y_amt.abs() > 0.0 {
+            self.mouse_x_remainder += rel_x_amt * pixels;
+            self.mouse_y_remainder += rel_y_amt * pixels;
+            let x_pix = self.mouse_x_remainder.trunc();
+            let y_pix = self.mouse_y_remainder.trunc();
+            self.mouse_x_remainder -= x_pix;
+            self.mouse_y_remainder -= y_pix;
+
+            if x_pix != 0.0 || y_pix != 0.0 {
+                self.enigo
+                    .move_mouse(x_pix as i32, y_pix as i32, Coordinate::Rel)
+                    .map_err(|e| e.to_string())?;
+                // The OS stops a relatively moved cursor at the edges by itself, and wrapping would
+                // make no sense for games that hide the pointer, so only the tracked position is clamped.
+                (self.mouse_x_pos, self.mouse_y_pos) = self.display_bounds.constrain(
+                    self.mouse_x_pos + x_pix,
+                    self.mouse_y_pos + y_pix,
+                    EdgeBehavior::Stop,
+                );
+            }
+        } else {
+            self.mouse_x_remainder = 0.0;
+            self.mouse_y_remainder = 0.0;
+        }
+
+        let (mouse_x_amt, mouse_y_amt) = self.get_stick_mouse_amount(elapsed, false);
         if mouse_x_amt.abs() > 0.0 || mouse_y_amt.abs() > 0.0 {
-            mouse_x_pos += mouse_x_amt;
-            mouse_y_pos += mouse_y_amt;
-            enigo
-                .move_mouse(mouse_x_pos as i32, mouse_y_pos as i32, Coordinate::Abs)
+            (self.mouse_x_pos, self.mouse_y_pos) = self.display_bounds.constrain(
+                self.mouse_x_pos + mouse_x_amt * pixels,
+                self.mouse_y_pos + mouse_y_amt * pixels,
+                self.profile_config.edge_behavior,
+            );
+            self.enigo
+                .move_mouse(
+                    self.mouse_x_pos as i32,
+                    self.mouse_y_pos as i32,
+                    Coordinate::Abs,
+                )
                 .map_err(|e| e.to_string())?;
         } else {
-            let (mouse_x_pix, mouse_y_pix) = enigo
+            let (mouse_x_pix, mouse_y_pix) = self
+                .enigo
                 .location()
-                .unwrap_or((mouse_x_pos as i32, mouse_y_pos as i32));
-            mouse_x_pos = mouse_x_pix as f32;
-            mouse_y_pos = mouse_y_pix as f32;
+                .unwrap_or((self.mouse_x_pos as i32, self.mouse_y_pos as i32));
+            self.mouse_x_pos = mouse_x_pix as f32;
+            self.mouse_y_pos = mouse_y_pix as f32;
+        }
+        Ok(())
+    }
+
+    fn scroll(&mut self, elapsed: Duration) -> Result<(), String> {
+        let mut horizontal_notches = 0;
+        let mut vertical_notches = 0;
+
+        for (&id, &i) in &self.assignments {
+            let (Some(agc), Some(gamepad)) = (
+                self.profile_config.get_gamepad_config(i),
+                self.gilrs.connected_gamepad(id),
+            ) else {
+                continue;
+            };
+
+            for joystick in [Joystick::Left, Joystick::Right] {
+                let sc = agc.get_stick_config(&joystick);
+                if sc.mode != StickMode::Scroll {
+                    continue;
+                }
+                let (x_axis, y_axis) = joystick.axes();
+                let (x_amt, y_amt) = sc.apply(gamepad.value(x_axis), gamepad.value(y_axis));
+                let (mut x_amt, mut y_amt) = sc.apply_response_curve(x_amt, y_amt);
+                if !sc.scroll_axes.scrolls_horizontally() {
+                    x_amt = 0.0;
+                }
+                if !sc.scroll_axes.scrolls_vertically() {
+                    y_amt = 0.0;
+                }
+
+                let notches = sc.scroll_speed * elapsed.as_secs_f32();
+                let stick_state = self.stick_states.entry((id, joystick)).or_default();
+                horizontal_notches +=
+                    stick_state
+                        .scroll_x
+                        .take_notches(x_amt, sc.scroll_style, notches);
+                // Tilting the stick up should scroll up, which takes negative notches.
+                vertical_notches +=
+                    stick_state
+                        .scroll_y
+                        .take_notches(-y_amt, sc.scroll_style, notches);
+            }
+        }
+
+        if horizontal_notches != 0 {
+            self.enigo
+                .scroll(horizontal_notches, Axis::Horizontal)
+                .map_err(|e| e.to_string())?;
+        }
+        if vertical_notches != 0 {
+            self.enigo
+                .scroll(vertical_notches, Axis::Vertical)
+                .map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    fn press_stick_keys(&mut self) -> Result<(), String> {
+        for (&id, &i) in &self.assignments {
+            let (Some(agc), Some(gamepad)) = (
+                self.profile_config.get_gamepad_config(i),
+                self.gilrs.connected_gamepad(id),
+            ) else {
+                continue;
+            };
+
+            for joystick in [Joystick::Left, Joystick::Right] {
+                let sc = agc.get_stick_config(&joystick);
+                if sc.mode != StickMode::Keys {
+                    continue;
+                }
+                let (x_axis, y_axis) = joystick.axes();
+                let (x_amt, y_amt) = sc.apply(gamepad.value(x_axis), gamepad.value(y_axis));
+
+                let stick_state = self.stick_states.entry((id, joystick)).or_default();
+                let directions = sc.get_key_directions(x_amt, y_amt, &stick_state.held_directions);
+                for direction in StickDirection::ALL {
+                    let source = InputSource::StickDirection(joystick, direction);
+                    let was_held = stick_state.held_directions.contains(&direction);
+                    let is_held = directions.contains(&direction);
+                    if is_held
+                        && !was_held
+                        && let Some(output) = sc.direction_keys.get(direction)
+                    {
+                        self.held_outputs
+                            .press(&mut self.enigo, id, source, output)?;
+                    } else if !is_held && was_held {
+                        self.held_outputs.release(&mut self.enigo, id, source)?;
+                    }
+                }
+                stick_state.held_directions = directions;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn run_controller_handler(
+    profile_config: ProfileConfig,
+    profile_updates: &Receiver<ProfileConfig>,
+    is_handler_running: &AtomicBool,
+    status_sender: &UnboundedSender<HandlerStatus>,
+) -> Result<(), String> {
+    let mut handler = ControllerHandler::new(profile_config, status_sender.clone())?;
+    handler.send_status(HandlerStatus::Started);
+    let result = handler.run(profile_updates, is_handler_running);
+
+    // Whatever made the handler stop, nothing it pressed may stay held at the OS level.
+    let release_result = handler.held_outputs.release_all(&mut handler.enigo);
+    result.and(release_result)
+}
+
+// Blocks until is_handler_running is cleared or an error occurs, so this is meant to be run on its
+// own thread. Progress is reported through status_sender, which always ends with Stopped.
+pub fn handle_controller_input(
+    profile_config: ProfileConfig,
+    profile_updates: Receiver<ProfileConfig>,
+    is_handler_running: Arc<AtomicBool>,
+    status_sender: UnboundedSender<HandlerStatus>,
+) {
+    let result = run_controller_handler(
+        profile_config,
+        &profile_updates,
+        &is_handler_running,
+        &status_sender,
+    );
+
+    is_handler_running.store(false, Ordering::Relaxed);
+    if let Err(e) = result {
+        let _ = status_sender.unbounded_send(HandlerStatus::Error(e));
+    }
+    let _ = status_sender.unbounded_send(HandlerStatus::Stopped);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::button_binding::{HoldAction, MultiTap};
+    use crate::backend::launch_command::LaunchCommand;
+    use enigo::Key;
+
+    fn key(c: char) -> MouseButtonOrKey {
+        MouseButtonOrKey::Key(Key::Unicode(c))
+    }
+
+    fn binding_with_hold() -> ButtonBinding {
+        ButtonBinding {
+            hold: Some(HoldAction {
+                output: Some(key('h')),
+                threshold_ms: 200,
+            }),
+            ..ButtonBinding::new(key('a'))
+        }
+    }
+
+    fn binding_with_multi_tap() -> ButtonBinding {
+        ButtonBinding {
+            multi_tap: Some(MultiTap {
+                double_tap: Some(key('d')),
+                triple_tap: Some(key('t')),
+                window_ms: 250,
+            }),
+            ..ButtonBinding::new(key('a'))
+        }
+    }
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn is_held_after_threshold_on_first_press_only() {
+        let start = Instant::now();
+        let mut pending = PendingButton::new(binding_with_hold(), start);
+        assert!(!pending.is_held(start + ms(199)));
+        assert!(pending.is_held(start + ms(200)));
+
+        pending.release(start + ms(100));
+        pending.press(start + ms(150));
+        assert!(!pending.is_held(start + ms(1000)));
+    }
+
+    #[test]
+    fn is_never_held_without_hold_action() {
+        let start = Instant::now();
+        let pending = PendingButton::new(binding_with_multi_tap(), start);
+        assert!(!pending.is_held(start + ms(5000)));
+    }
+
+    #[test]
+    fn release_after_threshold_is_hold() {
+        let start = Instant::now();
+        let mut pending = PendingButton::new(binding_with_hold(), start);
+        assert_eq!(pending.release(start + ms(210)), Some(PendingOutcome::Held));
+        assert_eq!(pending.taps, 0);
+        assert!(!pending.is_down);
+    }
+
+    #[test]
+    fn release_before_threshold_is_tap() {
+        let start = Instant::now();
+        let mut pending = PendingButton::new(binding_with_hold(), start);
+        assert_eq!(
+            pending.release(start + ms(50)),
+            Some(PendingOutcome::Tapped(1))
+        );
+    }
+
+    #[test]
+    fn is_tap_finished_waits_for_window() {
+        let start = Instant::now();
+        let mut pending = PendingButton::new(binding_with_multi_tap(), start);
+        assert!(!pending.is_tap_finished(start + ms(1000)));
+
+        assert_eq!(pending.release(start + ms(50)), None);
+        assert!(!pending.is_tap_finished(start + ms(299)));
+        assert!(pending.is_tap_finished(start + ms(300)));
+        assert_eq!(
+            pending.resolve(start + ms(300)),
+            Some(PendingOutcome::Tapped(1))
+        );
+    }
+
+    #[test]
+    fn is_tap_finished_at_max_taps() {
+        let start = Instant::now();
+        let mut pending = PendingButton::new(binding_with_multi_tap(), start);
+        assert_eq!(pending.release(start + ms(50)), None);
+        pending.press(start + ms(100));
+        assert!(!pending.is_tap_finished(start + ms(1000)));
+        assert_eq!(pending.release(start + ms(150)), None);
+        pending.press(start + ms(200));
+        assert_eq!(
+            pending.release(start + ms(250)),
+            Some(PendingOutcome::Tapped(3))
+        );
+        assert_eq!(
+            pending.get_output(PendingOutcome::Tapped(3)),
+            Some(&key('t'))
+        );
+    }
+
+    #[test]
+    fn command_double_tap_settles_once() {
+        let command = MouseButtonOrKey::Command(Box::new(LaunchCommand {
+            program: "notify-send".to_string(),
+            args: "double".to_string(),
+            working_dir: String::new(),
+        }));
+        let binding = ButtonBinding {
+            multi_tap: Some(MultiTap {
+                double_tap: Some(command.clone()),
+                triple_tap: Some(key('t')),
+                window_ms: 250,
+            }),
+            ..ButtonBinding::new(key('a'))
+        };
+
+        // Pressed and released twice, then left alone while the handler keeps ticking.
+        let start = Instant::now();
+        let mut pending_buttons = PendingButtons::new();
+        let mut settled = Vec::new();
+        pending_buttons.insert(Button::South, binding, start);
+        for (tick, at) in (0..100).map(|tick| (tick, start + ms(tick * 8))) {
+            match tick {
+                6 | 19 => assert!(pending_buttons.release(&Button::South, at)),
+                12 => assert!(pending_buttons.press(&Button::South, at)),
+                _ => {}
+            }
+            settled.extend(pending_buttons.take_settled(at));
         }
+
+        assert_eq!(
+            settled,
+            vec![(
+                Button::South,
+                SettledButton {
+                    output: command,
+                    is_down: false,
+                }
+            )]
+        );
+        // Once settled, the button is handled like any other again.
+        assert!(!pending_buttons.press(&Button::South, start + ms(1000)));
+    }
+
+    #[test]
+    fn hold_settles_while_down() {
+        let start = Instant::now();
+        let mut pending_buttons = PendingButtons::new();
+        pending_buttons.insert(Button::South, binding_with_hold(), start);
+        assert!(pending_buttons.take_settled(start + ms(100)).is_empty());
+        assert_eq!(
+            pending_buttons.take_settled(start + ms(200)),
+            vec![(
+                Button::South,
+                SettledButton {
+                    output: key('h'),
+                    is_down: true,
+                }
+            )]
+        );
+        // The release then goes to the held output rather than the pending button.
+        assert!(!pending_buttons.release(&Button::South, start + ms(300)));
+    }
+
+    fn take_notches_for(amts: &[f32], style: ScrollStyle, notches: f32) -> Vec<i32> {
+        let mut state = ScrollState::default();
+        amts.iter()
+            .map(|&amt| state.take_notches(amt, style, notches))
+            .collect()
+    }
+
+    #[test]
+    fn smooth_scroll_carries_fractions_over() {
+        assert_eq!(
+            take_notches_for(&[0.5; 4], ScrollStyle::Smooth, 0.5),
+            vec![0, 0, 0, 1]
+        );
+        assert_eq!(
+            take_notches_for(&[-1.0; 2], ScrollStyle::Smooth, 1.5),
+            vec![-1, -2]
+        );
+    }
+
+    #[test]
+    fn smooth_scroll_drops_fraction_when_centered() {
+        assert_eq!(
+            take_notches_for(&[0.25, 0.25, 0.25, 0.0, 0.25], ScrollStyle::Smooth, 1.0),
+            vec![0, 0, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn notch_scroll_steps_at_fixed_rate() {
+        // The first notch comes right away, however little the stick is tilted.
+        assert_eq!(
+            take_notches_for(&[0.1, 0.1, 0.9, 0.1, 0.1], ScrollStyle::Notches, 0.25),
+            vec![1, 0, 0, 0, 1]
+        );
+    }
+
+    #[test]
+    fn notch_scroll_restarts_on_direction_change() {
+        assert_eq!(
+            take_notches_for(&[0.5, 0.5, -0.5, 0.0, 0.5], ScrollStyle::Notches, 0.25),
+            vec![1, 0, -1, 0, 1]
+        );
     }
-    Ok(())
 }
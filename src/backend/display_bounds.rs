@@ -0,0 +1,303 @@
+use anyhow::Result;
+use enigo::{Enigo, Mouse};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use x11rb::connection::Connection;
+use x11rb::protocol::randr::ConnectionExt;
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub enum EdgeBehavior {
+    #[default]
+    Stop,
+    Wrap,
+}
+
+impl Display for EdgeBehavior {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EdgeBehavior::Stop => write!(f, "Stop"),
+            EdgeBehavior::Wrap => write!(f, "Wrap around"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct DisplayRect {
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+}
+
+impl DisplayRect {
+    // Positions are in whole pixels, so the last one is a pixel short of x + width.
+    fn right(&self) -> f32 {
+        self.x + self.width - 1.0
+    }
+
+    fn bottom(&self) -> f32 {
+        self.y + self.height - 1.0
+    }
+
+    fn clamp(&self, x: f32, y: f32) -> (f32, f32) {
+        (
+            x.clamp(self.x, self.right()),
+            y.clamp(self.y, self.bottom()),
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct DisplayBounds {
+    rects: Vec<DisplayRect>,
+}
+
+impl DisplayBounds {
+    // If neither X11 nor enigo can see a display, the cursor is left unbounded rather than trapped
+    // in a guessed area.
+    pub fn detect(enigo: &Enigo) -> Self {
+        let x11_rects = x11rb::connect(None)
+            .map_err(anyhow::Error::from)
+            .and_then(|(conn, screen_num)| get_x11_monitor_rects(&conn, screen_num));
+        let rects = match x11_rects {
+            Ok(rects) if !rects.is_empty() => rects,
+            _ => match enigo.main_display() {
+                Ok((width, height)) if width > 0 && height > 0 => vec![DisplayRect {
+                    x: 0.0,
+                    y: 0.0,
+                    width: width as f32,
+                    height: height as f32,
+                }],
+                _ => Vec::new(),
+            },
+        };
+        Self { rects }
+    }
+
+    // Gaps between monitors of different sizes count as outside, so the cursor slides along their
+    // edges instead of getting lost.
+    pub fn constrain(&self, x: f32, y: f32, edge_behavior: EdgeBehavior) -> (f32, f32) {
+        let (mut x, mut y) = (x, y);
+        if edge_behavior == EdgeBehavior::Wrap
+            && let Some((left, top, right, bottom)) = self.get_outer_bounds()
+        {
+            let (width, height) = (right - left + 1.0, bottom - top + 1.0);
+            if x < left {
+                x += width;
+            } else if x > right {
+                x -= width;
+            }
+            if y < top {
+                y += height;
+            } else if y > bottom {
+                y -= height;
+            }
+        }
+
+        self.rects
+            .iter()
+            .map(|rect| rect.clamp(x, y))
+            .min_by(|(ax, ay), (bx, by)| {
+                let a = (ax - x).hypot(ay - y);
+                let b = (bx - x).hypot(by - y);
+                a.total_cmp(&b)
+            })
+            .unwrap_or((x, y))
+    }
+
+    fn get_outer_bounds(&self) -> Option<(f32, f32, f32, f32)> {
+        let first = self.rects.first()?;
+        Some(self.rects.iter().fold(
+            (first.x, first.y, first.right(), first.bottom()),
+            |(left, top, right, bottom), rect| {
+                (
+                    left.min(rect.x),
+                    top.min(rect.y),
+                    right.max(rect.right()),
+                    bottom.max(rect.bottom()),
+                )
+            },
+        ))
+    }
+}
+
+// Re-reads the monitor layout on a thread of its own, since the round trips to X11 would otherwise
+// hold up the handler's tick.
+pub struct DisplayBoundsWatcher {
+    updated: Arc<Mutex<Option<DisplayBounds>>>,
+    // Nothing is ever sent, dropping the watcher disconnects it, which wakes the thread up to end.
+    _stop: Sender<()>,
+}
+
+impl DisplayBoundsWatcher {
+    pub fn start(interval: Duration) -> Self {
+        let updated = Arc::new(Mutex::new(None));
+        let (stop, stopped) = mpsc::channel();
+        let watched = updated.clone();
+        let _ = thread::Builder::new()
+            .name("display-bounds".to_string())
+            .spawn(move || {
+                // Without X11 there's nothing to watch, so the bounds detected at the start are kept.
+                let Ok((conn, screen_num)) = x11rb::connect(None) else {
+                    return;
+                };
+                while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                    if let Ok(rects) = get_x11_monitor_rects(&conn, screen_num)
+                        && !rects.is_empty()
+                    {
+                        *watched.lock().unwrap() = Some(DisplayBounds { rects });
+                    }
+                }
+            });
+        Self {
+            updated,
+            _stop: stop,
+        }
+    }
+
+    // Never blocks, so the tick can't wait on the watcher.
+    pub fn take_updated(&self) -> Option<DisplayBounds> {
+        self.updated.try_lock().ok()?.take()
+    }
+}
+
+fn get_x11_monitor_rects(conn: &impl Connection, screen_num: usize) -> Result<Vec<DisplayRect>> {
+    let screen = &conn.setup().roots[screen_num];
+    let monitors = conn
+        .randr_get_monitors(screen.root, true)?
+        .reply()?
+        .monitors;
+
+    if monitors.is_empty() {
+        // Without RandR monitors the whole root window is a single display.
+        return Ok(vec![DisplayRect {
+            x: 0.0,
+            y: 0.0,
+            width: screen.width_in_pixels as f32,
+            height: screen.height_in_pixels as f32,
+        }]);
+    }
+
+    Ok(monitors
+        .iter()
+        .filter(|monitor| monitor.width > 0 && monitor.height > 0)
+        .map(|monitor| DisplayRect {
+            x: monitor.x as f32,
+            y: monitor.y as f32,
+            width: monitor.width as f32,
+            height: monitor.height as f32,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A 1920x1080 monitor with a shorter 1280x1024 one to its right, which leaves a gap below the
+    // second one.
+    fn two_monitors() -> DisplayBounds {
+        DisplayBounds {
+            rects: vec![
+                DisplayRect {
+                    x: 0.0,
+                    y: 0.0,
+                    width: 1920.0,
+                    height: 1080.0,
+                },
+                DisplayRect {
+                    x: 1920.0,
+                    y: 0.0,
+                    width: 1280.0,
+                    height: 1024.0,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn constrain_leaves_positions_on_a_display() {
+        let bounds = two_monitors();
+        for edge_behavior in [EdgeBehavior::Stop, EdgeBehavior::Wrap] {
+            assert_eq!(
+                bounds.constrain(100.0, 200.0, edge_behavior),
+                (100.0, 200.0)
+            );
+            assert_eq!(
+                bounds.constrain(2500.0, 1023.0, edge_behavior),
+                (2500.0, 1023.0)
+            );
+        }
+    }
+
+    #[test]
+    fn constrain_stop_at_outer_edges() {
+        let bounds = two_monitors();
+        assert_eq!(
+            bounds.constrain(-10.0, 500.0, EdgeBehavior::Stop),
+            (0.0, 500.0)
+        );
+        assert_eq!(
+            bounds.constrain(3300.0, 500.0, EdgeBehavior::Stop),
+            (3199.0, 500.0)
+        );
+        assert_eq!(
+            bounds.constrain(100.0, 1200.0, EdgeBehavior::Stop),
+            (100.0, 1079.0)
+        );
+    }
+
+    #[test]
+    fn constrain_stop_slides_along_gap() {
+        let bounds = two_monitors();
+        assert_eq!(
+            bounds.constrain(2500.0, 1050.0, EdgeBehavior::Stop),
+            (2500.0, 1023.0)
+        );
+        // Closer to the first monitor's edge than to the second's bottom.
+        assert_eq!(
+            bounds.constrain(1925.0, 1075.0, EdgeBehavior::Stop),
+            (1919.0, 1075.0)
+        );
+    }
+
+    #[test]
+    fn constrain_wrap_across_outer_bounds() {
+        let bounds = two_monitors();
+        assert_eq!(
+            bounds.constrain(3250.0, 500.0, EdgeBehavior::Wrap),
+            (50.0, 500.0)
+        );
+        assert_eq!(
+            bounds.constrain(-10.0, 500.0, EdgeBehavior::Wrap),
+            (3190.0, 500.0)
+        );
+        assert_eq!(
+            bounds.constrain(100.0, -5.0, EdgeBehavior::Wrap),
+            (100.0, 1075.0)
+        );
+    }
+
+    #[test]
+    fn constrain_wrap_into_gap() {
+        let bounds = two_monitors();
+        // Wrapping to the bottom of the desktop lands in the gap below the second monitor.
+        assert_eq!(
+            bounds.constrain(2500.0, -5.0, EdgeBehavior::Wrap),
+            (2500.0, 1023.0)
+        );
+    }
+
+    #[test]
+    fn constrain_without_displays_is_unbounded() {
+        let bounds = DisplayBounds::default();
+        assert_eq!(
+            bounds.constrain(-5000.0, 9000.0, EdgeBehavior::Wrap),
+            (-5000.0, 9000.0)
+        );
+    }
+}
@@ -0,0 +1,69 @@
+use anyhow::{Result, anyhow};
+use gilrs::{Gamepad, Gilrs};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub enum GamepadMatcher {
+    Uuid(String),
+    Name(String),
+    // Claims whichever gamepad is left over once the more specific rules have been applied.
+    #[default]
+    Any,
+}
+
+impl GamepadMatcher {
+    // Lower values are more specific and get to claim their gamepad first.
+    pub fn priority(&self) -> u8 {
+        match self {
+            GamepadMatcher::Uuid(_) => 0,
+            GamepadMatcher::Name(_) => 1,
+            GamepadMatcher::Any => 2,
+        }
+    }
+
+    pub fn matches(&self, gamepad: &ConnectedGamepad) -> bool {
+        match self {
+            GamepadMatcher::Uuid(uuid) => *uuid == gamepad.uuid,
+            GamepadMatcher::Name(name) => *name == gamepad.name,
+            GamepadMatcher::Any => true,
+        }
+    }
+}
+
+impl Display for GamepadMatcher {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GamepadMatcher::Uuid(uuid) => write!(f, "UUID {}", uuid),
+            GamepadMatcher::Name(name) => write!(f, "Name \"{}\"", name),
+            GamepadMatcher::Any => write!(f, "Any"),
+        }
+    }
+}
+
+pub fn format_uuid(uuid: [u8; 16]) -> String {
+    uuid.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConnectedGamepad {
+    pub name: String,
+    pub uuid: String,
+}
+
+impl ConnectedGamepad {
+    pub fn new(gamepad: &Gamepad) -> Self {
+        Self {
+            name: gamepad.name().to_string(),
+            uuid: format_uuid(gamepad.uuid()),
+        }
+    }
+}
+
+pub fn get_connected_gamepads() -> Result<Vec<ConnectedGamepad>> {
+    let gilrs = Gilrs::new().map_err(|e| anyhow!("ERROR: Couldn't list controllers: {e}"))?;
+    Ok(gilrs
+        .gamepads()
+        .map(|(_, gamepad)| ConnectedGamepad::new(&gamepad))
+        .collect())
+}
@@ -0,0 +1,193 @@
+use crate::backend::input_source::InputSource;
+use crate::backend::key_utils::MouseButtonOrKey;
+use crate::backend::launch_command::LaunchCommand;
+use crate::backend::macros::MacroRun;
+use enigo::{Direction, Enigo};
+use gilrs::GamepadId;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Instant;
+
+// Keeps track of every key and mouse button the handler has pressed but not released yet, so they
+// can't get stuck at the OS level when a controller, the profile or the handler itself goes away.
+#[derive(Default)]
+pub struct HeldOutputs {
+    // The output is remembered per input rather than looked up again on release, since
+    // the binding might have changed in the meantime.
+    held: HashMap<(GamepadId, InputSource), MouseButtonOrKey>,
+    macro_runs: HashMap<(GamepadId, InputSource), MacroRun>,
+    // Unlike other failures, failed commands don't stop the handler. Shared with the threads that
+    // wait for the commands to exit.
+    command_errors: Arc<Mutex<Vec<String>>>,
+}
+
+impl HeldOutputs {
+    pub fn press(
+        &mut self,
+        enigo: &mut Enigo,
+        id: GamepadId,
+        source: InputSource,
+        output: &MouseButtonOrKey,
+    ) -> Result<(), String> {
+        // A repeated press (e.g. after a profile swap) must not stack up a second press.
+        self.stop(enigo, id, source)?;
+        if let MouseButtonOrKey::Macro(m) = output {
+            return self.start_macro(enigo, id, source, MacroRun::new(m.clone()));
+        }
+        if let MouseButtonOrKey::Command(command) = output {
+            self.run_command(command);
+            return Ok(());
+        }
+        output.perform(enigo, Direction::Press)?;
+        self.held.insert((id, source), output.clone());
+        Ok(())
+    }
+
+    // For an input that was already released by the time its output was known. A macro plays to its
+    // end, as it would otherwise be cut off at its first wait.
+    pub fn tap(
+        &mut self,
+        enigo: &mut Enigo,
+        id: GamepadId,
+        source: InputSource,
+        output: &MouseButtonOrKey,
+    ) -> Result<(), String> {
+        if let MouseButtonOrKey::Macro(m) = output {
+            self.stop(enigo, id, source)?;
+            let mut run = MacroRun::new(m.clone());
+            run.release();
+            return self.start_macro(enigo, id, source, run);
+        }
+        self.press(enigo, id, source, output)?;
+        self.release(enigo, id, source)
+    }
+
+    fn start_macro(
+        &mut self,
+        enigo: &mut Enigo,
+        id: GamepadId,
+        source: InputSource,
+        mut run: MacroRun,
+    ) -> Result<(), String> {
+        if !run.advance(enigo, Instant::now())? {
+            self.macro_runs.insert((id, source), run);
+        }
+        Ok(())
+    }
+
+    // Every command is waited for on a thread of its own, so it's reaped whenever it exits instead of
+    // lingering as a zombie, even if the handler has stopped by then.
+    pub fn run_command(&mut self, command: &LaunchCommand) {
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                self.command_errors.lock().unwrap().push(e);
+                return;
+            }
+        };
+
+        let program = command.program.trim().to_string();
+        let command_errors = self.command_errors.clone();
+        let spawn_result = thread::Builder::new()
+            .name(format!("command-{}", child.id()))
+            .spawn(move || {
+                let error = match child.wait() {
+                    Ok(status) if status.success() => return,
+                    Ok(status) => format!("{} exited with {}", program, status),
+                    Err(e) => e.to_string(),
+                };
+                command_errors.lock().unwrap().push(error);
+            });
+        if let Err(e) = spawn_result {
+            self.command_errors.lock().unwrap().push(e.to_string());
+        }
+    }
+
+    pub fn tick(&mut self, enigo: &mut Enigo) -> Result<(), String> {
+        let now = Instant::now();
+        let mut result = Ok(());
+        self.macro_runs
+            .retain(|_, run| match run.advance(enigo, now) {
+                Ok(is_finished) => !is_finished,
+                Err(e) => {
+                    // A macro that failed once would most likely keep failing on every tick.
+                    let _ = run.cancel(enigo);
+                    result = Err(e);
+                    false
+                }
+            });
+        result
+    }
+
+    pub fn take_command_errors(&mut self) -> Vec<String> {
+        std::mem::take(&mut *self.command_errors.lock().unwrap())
+    }
+
+    pub fn release(
+        &mut self,
+        enigo: &mut Enigo,
+        id: GamepadId,
+        source: InputSource,
+    ) -> Result<(), String> {
+        if let Some(run) = self.macro_runs.get_mut(&(id, source)) {
+            if run.cancels_on_release() {
+                return self.stop(enigo, id, source);
+            }
+            // Otherwise the macro plays on until its last step, just without repeating.
+            run.release();
+        }
+        if let Some(output) = self.held.remove(&(id, source)) {
+            output.perform(enigo, Direction::Release)?;
+        }
+        Ok(())
+    }
+
+    // Unlike release, this also cuts short any macro the input started.
+    fn stop(
+        &mut self,
+        enigo: &mut Enigo,
+        id: GamepadId,
+        source: InputSource,
+    ) -> Result<(), String> {
+        let mut result = Ok(());
+        if let Some(mut run) = self.macro_runs.remove(&(id, source)) {
+            result = run.cancel(enigo);
+        }
+        if let Some(output) = self.held.remove(&(id, source)) {
+            result = result.and(output.perform(enigo, Direction::Release));
+        }
+        result
+    }
+
+    pub fn release_gamepad(&mut self, enigo: &mut Enigo, id: GamepadId) -> Result<(), String> {
+        self.release_where(enigo, |(held_id, _)| *held_id == id)
+    }
+
+    pub fn release_all(&mut self, enigo: &mut Enigo) -> Result<(), String> {
+        self.release_where(enigo, |_| true)
+    }
+
+    pub fn release_where(
+        &mut self,
+        enigo: &mut Enigo,
+        predicate: impl Fn(&(GamepadId, InputSource)) -> bool,
+    ) -> Result<(), String> {
+        let sources: Vec<(GamepadId, InputSource)> = self
+            .held
+            .keys()
+            .chain(self.macro_runs.keys())
+            .filter(|source| predicate(source))
+            .copied()
+            .collect();
+
+        // Keep going on failure so that one bad release doesn't leave everything else held.
+        let mut result = Ok(());
+        for (id, source) in sources {
+            if let Err(e) = self.stop(enigo, id, source) {
+                result = result.and(Err(e));
+            }
+        }
+        result
+    }
+}
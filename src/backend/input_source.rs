@@ -0,0 +1,39 @@
+use crate::backend::axis_binding::AxisHalf;
+use crate::backend::button_binding::ExtraOutput;
+use crate::backend::joysticks::{Joystick, StickDirection};
+use gilrs::{Axis, Button};
+use std::fmt::{Display, Formatter};
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum InputSource {
+    Button(Button),
+    StickDirection(Joystick, StickDirection),
+    Axis(Axis, AxisHalf),
+}
+
+impl Display for InputSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InputSource::Button(btn) => write!(f, "{:?}", btn),
+            InputSource::StickDirection(js, direction) => write!(f, "{:?} Stick {}", js, direction),
+            InputSource::Axis(axis, AxisHalf::Positive) => write!(f, "{:?} +", axis),
+            InputSource::Axis(axis, AxisHalf::Negative) => write!(f, "{:?} -", axis),
+        }
+    }
+}
+
+// Where an output is bound, either as the output of an input or as one of a button's extra outputs.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum OutputSlot {
+    Input(InputSource),
+    Extra(Button, ExtraOutput),
+}
+
+impl Display for OutputSlot {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutputSlot::Input(source) => write!(f, "{}", source),
+            OutputSlot::Extra(btn, extra) => write!(f, "{:?} ({})", btn, extra),
+        }
+    }
+}
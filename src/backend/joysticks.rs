@@ -1,5 +1,54 @@
-#[derive(Clone, Debug, Eq, PartialEq)]
+use gilrs::Axis;
+use std::fmt::{Display, Formatter};
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum Joystick {
     Left,
     Right,
 }
+
+impl Joystick {
+    pub fn axes(&self) -> (Axis, Axis) {
+        match self {
+            Joystick::Left => (Axis::LeftStickX, Axis::LeftStickY),
+            Joystick::Right => (Axis::RightStickX, Axis::RightStickY),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum StickDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl StickDirection {
+    pub const ALL: [StickDirection; 4] = [
+        StickDirection::Up,
+        StickDirection::Down,
+        StickDirection::Left,
+        StickDirection::Right,
+    ];
+
+    pub fn get_component(&self, x: f32, y: f32) -> f32 {
+        match self {
+            StickDirection::Up => y,
+            StickDirection::Down => -y,
+            StickDirection::Left => -x,
+            StickDirection::Right => x,
+        }
+    }
+}
+
+impl Display for StickDirection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StickDirection::Up => write!(f, "Up"),
+            StickDirection::Down => write!(f, "Down"),
+            StickDirection::Left => write!(f, "Left"),
+            StickDirection::Right => write!(f, "Right"),
+        }
+    }
+}
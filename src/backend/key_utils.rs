@@ -1,36 +1,257 @@
+use crate::backend::launch_command::LaunchCommand;
+use crate::backend::macros::Macro;
+use crate::backend::physical_keys::{describe_x11_keycode, get_x11_keycode};
 use enigo::Key::Unicode;
-use enigo::{Button as MouseButton, Key};
+use enigo::{Button as MouseButton, Direction, Enigo, Key, Keyboard, Mouse};
 use iced::keyboard::Key as IcedKey;
 use iced::keyboard::Key::Character;
-use iced::keyboard::key::Named;
+use iced::keyboard::Location;
+use iced::keyboard::Modifiers;
+use iced::keyboard::key::{Code, Named, Physical};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 
-pub fn get_enigo_key_from_iced_key(key: IcedKey) -> Option<Key> {
-    if let Character(c) = key {
-        Some(Unicode(c.chars().next()?))
-    } else if let IcedKey::Named(named) = key {
-        let named_chars_map: HashMap<Named, Key> = HashMap::from([
-            (Named::Control, Key::Control),
-            (Named::Alt, Key::Alt),
-            (Named::Shift, Key::Shift),
-            (Named::Tab, Key::Tab),
-            (Named::Escape, Key::Escape),
-            (Named::Meta, Key::Meta),
-            (Named::Backspace, Key::Backspace),
-        ]);
+// Returns None for keys enigo has no equivalent for, so they can be reported instead of dropped.
+fn get_enigo_key_from_iced_key(key: IcedKey, location: Location) -> Option<Key> {
+    match key {
+        Character(c) if location == Location::Numpad => get_numpad_key(&c),
+        Character(c) => Some(Unicode(c.chars().next()?)),
+        IcedKey::Named(named) => get_named_key(named, location),
+        IcedKey::Unidentified => None,
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CapturedKey {
+    pub key: IcedKey,
+    pub location: Location,
+    pub physical_key: Physical,
+    pub modifiers: Modifiers,
+}
+
+impl CapturedKey {
+    pub fn is_modifier(&self) -> bool {
+        matches!(
+            self.key,
+            IcedKey::Named(Named::Control | Named::Alt | Named::Shift | Named::Meta | Named::Super)
+        )
+    }
+
+    pub fn to_output(&self, physical: bool) -> Option<MouseButtonOrKey> {
+        if physical {
+            get_x11_keycode(&self.physical_key).map(MouseButtonOrKey::RawKey)
+        } else {
+            get_enigo_key_from_iced_key(self.key.clone(), self.location).map(MouseButtonOrKey::Key)
+        }
+    }
+
+    pub fn to_combo(&self, physical: bool) -> Option<MouseButtonOrKey> {
+        let mut outputs = get_modifier_outputs(self.modifiers, physical);
+        outputs.push(self.to_output(physical)?);
+        MouseButtonOrKey::from_outputs(outputs)
+    }
+
+    pub fn describe(&self) -> String {
+        describe_iced_key(&self.key)
+    }
+}
+
+// Modifiers are always pressed in the same order, whichever was held down first.
+fn get_modifier_outputs(modifiers: Modifiers, physical: bool) -> Vec<MouseButtonOrKey> {
+    [
+        (modifiers.control(), Key::Control, Code::ControlLeft),
+        (modifiers.alt(), Key::Alt, Code::AltLeft),
+        (modifiers.shift(), Key::Shift, Code::ShiftLeft),
+        (modifiers.logo(), Key::Meta, Code::SuperLeft),
+    ]
+    .into_iter()
+    .filter(|(is_held, _, _)| *is_held)
+    .filter_map(|(_, key, code)| {
+        if physical {
+            get_x11_keycode(&Physical::Code(code)).map(MouseButtonOrKey::RawKey)
+        } else {
+            Some(MouseButtonOrKey::Key(key))
+        }
+    })
+    .collect()
+}
 
-        Some(*named_chars_map.get(&named)?)
-    } else {
-        None
+fn describe_iced_key(key: &IcedKey) -> String {
+    match key {
+        Character(c) => format!("\"{}\"", c),
+        IcedKey::Named(named) => format!("{:?}", named),
+        IcedKey::Unidentified => "This key".to_string(),
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+// The numpad reports the same characters as the main keyboard, while enigo has keys of its own
+// for it. Without NumLock it reports named keys (arrows, Home, ...) instead, which aren't handled here.
+fn get_numpad_key(c: &str) -> Option<Key> {
+    let key = match c {
+        "0" => Key::Numpad0,
+        "1" => Key::Numpad1,
+        "2" => Key::Numpad2,
+        "3" => Key::Numpad3,
+        "4" => Key::Numpad4,
+        "5" => Key::Numpad5,
+        "6" => Key::Numpad6,
+        "7" => Key::Numpad7,
+        "8" => Key::Numpad8,
+        "9" => Key::Numpad9,
+        "+" => Key::Add,
+        "-" => Key::Subtract,
+        "*" => Key::Multiply,
+        "/" => Key::Divide,
+        "." | "," => Key::Decimal,
+        _ => Unicode(c.chars().next()?),
+    };
+    Some(key)
+}
+
+fn get_named_key(named: Named, location: Location) -> Option<Key> {
+    let key = match named {
+        // Modifiers
+        Named::Alt => Key::Alt,
+        Named::Control if location == Location::Right => Key::RControl,
+        Named::Control if location == Location::Left => Key::LControl,
+        Named::Control => Key::Control,
+        Named::Shift if location == Location::Right => Key::RShift,
+        Named::Shift if location == Location::Left => Key::LShift,
+        Named::Shift => Key::Shift,
+        Named::Meta | Named::Super => Key::Meta,
+        Named::CapsLock => Key::CapsLock,
+        Named::NumLock => Key::Numlock,
+        Named::ScrollLock => Key::ScrollLock,
+        // Whitespace and editing
+        Named::Enter => Key::Return,
+        Named::Tab => Key::Tab,
+        Named::Space => Key::Space,
+        Named::Backspace => Key::Backspace,
+        Named::Delete => Key::Delete,
+        Named::Insert => Key::Insert,
+        Named::Clear => Key::Clear,
+        Named::Undo => Key::Undo,
+        Named::Redo => Key::Redo,
+        // Navigation
+        Named::ArrowUp => Key::UpArrow,
+        Named::ArrowDown => Key::DownArrow,
+        Named::ArrowLeft => Key::LeftArrow,
+        Named::ArrowRight => Key::RightArrow,
+        Named::Home => Key::Home,
+        Named::End => Key::End,
+        Named::PageUp => Key::PageUp,
+        Named::PageDown => Key::PageDown,
+        // UI and device keys
+        Named::Escape => Key::Escape,
+        Named::Cancel => Key::Cancel,
+        Named::Execute => Key::Execute,
+        Named::Find => Key::Find,
+        Named::Help => Key::Help,
+        Named::Pause => Key::Pause,
+        Named::Select => Key::Select,
+        Named::PrintScreen => Key::PrintScr,
+        Named::ModeChange => Key::ModeChange,
+        Named::HangulMode => Key::Hangul,
+        Named::HanjaMode => Key::Hanja,
+        Named::KanjiMode => Key::Kanji,
+        // Media
+        Named::MediaPlayPause => Key::MediaPlayPause,
+        Named::MediaStop => Key::MediaStop,
+        Named::MediaTrackNext => Key::MediaNextTrack,
+        Named::MediaTrackPrevious => Key::MediaPrevTrack,
+        Named::AudioVolumeUp => Key::VolumeUp,
+        Named::AudioVolumeDown => Key::VolumeDown,
+        Named::AudioVolumeMute => Key::VolumeMute,
+        Named::MicrophoneVolumeMute => Key::MicMute,
+        // Function keys
+        Named::F1 => Key::F1,
+        Named::F2 => Key::F2,
+        Named::F3 => Key::F3,
+        Named::F4 => Key::F4,
+        Named::F5 => Key::F5,
+        Named::F6 => Key::F6,
+        Named::F7 => Key::F7,
+        Named::F8 => Key::F8,
+        Named::F9 => Key::F9,
+        Named::F10 => Key::F10,
+        Named::F11 => Key::F11,
+        Named::F12 => Key::F12,
+        Named::F13 => Key::F13,
+        Named::F14 => Key::F14,
+        Named::F15 => Key::F15,
+        Named::F16 => Key::F16,
+        Named::F17 => Key::F17,
+        Named::F18 => Key::F18,
+        Named::F19 => Key::F19,
+        Named::F20 => Key::F20,
+        Named::F21 => Key::F21,
+        Named::F22 => Key::F22,
+        Named::F23 => Key::F23,
+        Named::F24 => Key::F24,
+        Named::F25 => Key::F25,
+        Named::F26 => Key::F26,
+        Named::F27 => Key::F27,
+        Named::F28 => Key::F28,
+        Named::F29 => Key::F29,
+        Named::F30 => Key::F30,
+        Named::F31 => Key::F31,
+        Named::F32 => Key::F32,
+        Named::F33 => Key::F33,
+        Named::F34 => Key::F34,
+        Named::F35 => Key::F35,
+        _ => return None,
+    };
+    Some(key)
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum MouseButtonOrKey {
     MouseButton(MouseButton),
     Key(Key),
+    // An X11 keycode, which names a physical key rather than the character the layout puts on it.
+    RawKey(u16),
+    // Pressed together in order and released in reverse, e.g. Control + Shift + T.
+    Combo(Vec<MouseButtonOrKey>),
+    Macro(Macro),
+    // Typed in one go when pressed, which also works for Unicode the keyboard layout can't type.
+    Text(String),
+    // Boxed since it's a lot bigger than the other outputs.
+    Command(Box<LaunchCommand>),
+}
+
+impl MouseButtonOrKey {
+    pub fn from_outputs(mut outputs: Vec<MouseButtonOrKey>) -> Option<Self> {
+        match outputs.len() {
+            0 => None,
+            1 => outputs.pop(),
+            _ => Some(MouseButtonOrKey::Combo(outputs)),
+        }
+    }
+
+    pub fn perform(&self, enigo: &mut Enigo, dir: Direction) -> Result<(), String> {
+        match self {
+            MouseButtonOrKey::Combo(outputs) => match dir {
+                Direction::Press => outputs.iter().try_for_each(|o| o.perform(enigo, dir)),
+                Direction::Release => outputs.iter().rev().try_for_each(|o| o.perform(enigo, dir)),
+                Direction::Click => {
+                    self.perform(enigo, Direction::Press)?;
+                    self.perform(enigo, Direction::Release)
+                }
+            },
+            MouseButtonOrKey::Macro(_) => Err("A macro can't be pressed like a key!".to_string()),
+            MouseButtonOrKey::Command(_) => {
+                Err("A command can't be pressed like a key!".to_string())
+            }
+            MouseButtonOrKey::MouseButton(mb) => enigo.button(*mb, dir).map_err(|e| e.to_string()),
+            MouseButtonOrKey::Key(key) => enigo.key(*key, dir).map_err(|e| e.to_string()),
+            MouseButtonOrKey::RawKey(keycode) => {
+                enigo.raw(*keycode, dir).map_err(|e| e.to_string())
+            }
+            // There's nothing to release once the text has been typed.
+            MouseButtonOrKey::Text(text) if dir == Direction::Release || text.is_empty() => Ok(()),
+            MouseButtonOrKey::Text(text) => enigo.text(text).map_err(|e| e.to_string()),
+        }
+    }
 }
 
 impl Display for MouseButtonOrKey {
@@ -44,6 +265,16 @@ impl Display for MouseButtonOrKey {
                     write!(f, "{:?}", k)
                 }
             }
+            MouseButtonOrKey::RawKey(keycode) => {
+                write!(f, "{} (Physical Key)", describe_x11_keycode(*keycode))
+            }
+            MouseButtonOrKey::Combo(outputs) => {
+                let names: Vec<String> = outputs.iter().map(|o| o.to_string()).collect();
+                write!(f, "{}", names.join(" + "))
+            }
+            MouseButtonOrKey::Macro(m) => write!(f, "Macro ({} steps)", m.steps.len()),
+            MouseButtonOrKey::Text(text) => write!(f, "Type \"{}\"", text),
+            MouseButtonOrKey::Command(command) => write!(f, "{}", command),
         }
     }
 }
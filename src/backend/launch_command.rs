@@ -0,0 +1,110 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command, Stdio};
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LaunchCommand {
+    pub program: String,
+    // Kept as typed so it stays editable, and split up when the command is run.
+    pub args: String,
+    // The program's working directory, empty to use the mapper's own.
+    pub working_dir: String,
+}
+
+impl LaunchCommand {
+    pub fn spawn(&self) -> Result<Child, String> {
+        let program = self.program.trim();
+        if program.is_empty() {
+            return Err("No program was set for the command!".to_string());
+        }
+
+        let mut command = Command::new(program);
+        command
+            .args(split_args(&self.args))
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            // A process group of its own keeps it running when the mapper is stopped with Ctrl+C.
+            .process_group(0);
+        if !self.working_dir.trim().is_empty() {
+            command.current_dir(self.working_dir.trim());
+        }
+        command
+            .spawn()
+            .map_err(|e| format!("Couldn't run {}: {}", program, e))
+    }
+}
+
+impl Display for LaunchCommand {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.args.trim().is_empty() {
+            write!(f, "Run \"{}\"", self.program.trim())
+        } else {
+            write!(f, "Run \"{} {}\"", self.program.trim(), self.args.trim())
+        }
+    }
+}
+
+fn split_args(args: &str) -> Vec<String> {
+    let mut split = Vec::new();
+    let mut current = String::new();
+    // Tracked separately from current so that "" still counts as an (empty) argument.
+    let mut has_arg = false;
+    let mut in_quotes = false;
+
+    for c in args.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_arg = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if has_arg {
+                    split.push(std::mem::take(&mut current));
+                    has_arg = false;
+                }
+            }
+            c => {
+                current.push(c);
+                has_arg = true;
+            }
+        }
+    }
+    if has_arg {
+        split.push(current);
+    }
+    split
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_args_on_whitespace() {
+        assert_eq!(split_args("  -a  --b\tc "), vec!["-a", "--b", "c"]);
+    }
+
+    #[test]
+    fn split_args_keeps_quoted_together() {
+        assert_eq!(
+            split_args(r#"-e "echo hi" x"y z""#),
+            vec!["-e", "echo hi", "xy z"]
+        );
+    }
+
+    #[test]
+    fn split_args_empty() {
+        assert!(split_args("").is_empty());
+        assert!(split_args("   ").is_empty());
+        assert_eq!(split_args(r#"a "" b"#), vec!["a", "", "b"]);
+    }
+
+    #[test]
+    fn split_args_unterminated_quote_runs_to_end() {
+        assert_eq!(split_args(r#"a "b c"#), vec!["a", "b c"]);
+        assert_eq!(split_args(r#"""#), vec![""]);
+    }
+}
@@ -0,0 +1,382 @@
+use crate::backend::key_utils::{CapturedKey, MouseButtonOrKey};
+use crate::backend::macros::{Macro, MacroStep};
+use enigo::Button as MouseButton;
+use iced::keyboard::key::Physical;
+use iced::{Event, keyboard, mouse};
+use std::fmt::{Display, Formatter};
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum RecordedDelays {
+    #[default]
+    AsRecorded,
+    // Multiplies every delay, e.g. 0.5 plays the macro back twice as fast.
+    Scaled(f32),
+    Fixed(u64),
+}
+
+impl RecordedDelays {
+    pub const MAX_SCALE: f32 = 4.0;
+
+    pub fn options() -> [RecordedDelays; 3] {
+        [
+            RecordedDelays::AsRecorded,
+            RecordedDelays::Scaled(1.0),
+            RecordedDelays::Fixed(50),
+        ]
+    }
+
+    fn apply(&self, delay: Duration) -> u64 {
+        match self {
+            RecordedDelays::AsRecorded => delay.as_millis() as u64,
+            RecordedDelays::Scaled(scale) => (delay.as_secs_f32() * scale * 1000.0).round() as u64,
+            RecordedDelays::Fixed(ms) => *ms,
+        }
+    }
+}
+
+impl Display for RecordedDelays {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecordedDelays::AsRecorded => write!(f, "As recorded"),
+            RecordedDelays::Scaled(_) => write!(f, "Scaled"),
+            RecordedDelays::Fixed(_) => write!(f, "Fixed"),
+        }
+    }
+}
+
+// What a release is matched to its press by. Keys go by their physical key, since the logical key
+// can change in between, e.g. when Shift is pressed before `a` is released, or the layout changes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum RecordedInput {
+    Key(Physical),
+    MouseButton(mouse::Button),
+}
+
+#[derive(Default)]
+pub struct MacroRecorder {
+    is_recording: bool,
+    // Each step with the time since the step before it.
+    steps: Vec<(Duration, MacroStep)>,
+    last_step_at: Option<Instant>,
+    held: Vec<(RecordedInput, MouseButtonOrKey)>,
+    skipped_keys: usize,
+    pub delays: RecordedDelays,
+}
+
+impl MacroRecorder {
+    pub fn start(&mut self) {
+        self.clear();
+        self.is_recording = true;
+    }
+
+    pub fn stop(&mut self) {
+        self.is_recording = false;
+    }
+
+    // Throws away the last recording but keeps the delay settings.
+    pub fn clear(&mut self) {
+        self.is_recording = false;
+        self.steps.clear();
+        self.last_step_at = None;
+        self.held.clear();
+        self.skipped_keys = 0;
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.is_recording
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    pub fn skipped_keys(&self) -> usize {
+        self.skipped_keys
+    }
+
+    pub fn record(&mut self, event: &Event, at: Instant, physical: bool) {
+        if !self.is_recording {
+            return;
+        }
+
+        match event {
+            Event::Keyboard(keyboard::Event::KeyPressed {
+                key,
+                location,
+                physical_key,
+                modifiers,
+                repeat: false,
+                ..
+            }) => {
+                let captured = CapturedKey {
+                    key: key.clone(),
+                    location: *location,
+                    physical_key: *physical_key,
+                    modifiers: *modifiers,
+                };
+                match captured.to_output(physical) {
+                    Some(output) => self.press(RecordedInput::Key(*physical_key), output, at),
+                    None => self.skipped_keys += 1,
+                }
+            }
+            Event::Keyboard(keyboard::Event::KeyReleased { physical_key, .. }) => {
+                self.release(RecordedInput::Key(*physical_key), at);
+            }
+            Event::Mouse(mouse::Event::ButtonPressed(button)) => {
+                if let Some(mb) = get_enigo_mouse_button(*button) {
+                    let output = MouseButtonOrKey::MouseButton(mb);
+                    self.press(RecordedInput::MouseButton(*button), output, at);
+                }
+            }
+            Event::Mouse(mouse::Event::ButtonReleased(button)) => {
+                self.release(RecordedInput::MouseButton(*button), at);
+            }
+            // Pixel based scrolling from touchpads has no notches to click, so only wheels are recorded.
+            Event::Mouse(mouse::Event::WheelScrolled {
+                delta: mouse::ScrollDelta::Lines { x, y },
+            }) => {
+                for (lines, positive, negative) in [
+                    (*y, MouseButton::ScrollUp, MouseButton::ScrollDown),
+                    (*x, MouseButton::ScrollRight, MouseButton::ScrollLeft),
+                ] {
+                    let mb = if lines > 0.0 { positive } else { negative };
+                    for _ in 0..lines.abs().round() as usize {
+                        self.push_step(MacroStep::Click(mb), at);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn press(&mut self, input: RecordedInput, output: MouseButtonOrKey, at: Instant) {
+        // Key repeat sends more presses while a key is held, which mustn't turn into extra steps.
+        if self.held.iter().any(|(held, _)| *held == input) {
+            return;
+        }
+        self.held.push((input, output.clone()));
+        self.push_step(MacroStep::KeyDown(Some(output)), at);
+    }
+
+    fn release(&mut self, input: RecordedInput, at: Instant) {
+        // Keys that were already down when the recording started are left out altogether.
+        let Some(i) = self.held.iter().position(|(held, _)| *held == input) else {
+            return;
+        };
+        let (_, output) = self.held.remove(i);
+        self.push_step(MacroStep::KeyUp(Some(output)), at);
+    }
+
+    fn push_step(&mut self, step: MacroStep, at: Instant) {
+        let delay = self
+            .last_step_at
+            .map_or(Duration::ZERO, |last| at.saturating_duration_since(last));
+        self.steps.push((delay, step));
+        self.last_step_at = Some(at);
+    }
+
+    pub fn to_macro(&self) -> Macro {
+        let mut steps = Vec::new();
+        for (i, (delay, step)) in self.steps.iter().enumerate() {
+            // The time before the first event is just how long it took to get started.
+            let ms = self.delays.apply(*delay);
+            if i > 0 && ms > 0 {
+                steps.push(MacroStep::Wait(ms));
+            }
+            steps.push(step.clone());
+        }
+        // Anything still held when the recording stopped is released at the end.
+        for (_, output) in self.held.iter().rev() {
+            steps.push(MacroStep::KeyUp(Some(output.clone())));
+        }
+
+        Macro {
+            steps,
+            ..Macro::default()
+        }
+    }
+}
+
+fn get_enigo_mouse_button(button: mouse::Button) -> Option<MouseButton> {
+    match button {
+        mouse::Button::Left => Some(MouseButton::Left),
+        mouse::Button::Right => Some(MouseButton::Right),
+        mouse::Button::Middle => Some(MouseButton::Middle),
+        mouse::Button::Back => Some(MouseButton::Back),
+        mouse::Button::Forward => Some(MouseButton::Forward),
+        mouse::Button::Other(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use enigo::Key;
+    use iced::keyboard::key::{Code, Named};
+    use iced::keyboard::{Location, Modifiers};
+
+    fn key_event(key: keyboard::Key, code: Code, modifiers: Modifiers, is_press: bool) -> Event {
+        let physical_key = Physical::Code(code);
+        let location = Location::Standard;
+        Event::Keyboard(if is_press {
+            keyboard::Event::KeyPressed {
+                key: key.clone(),
+                modified_key: key,
+                physical_key,
+                location,
+                modifiers,
+                text: None,
+                repeat: false,
+            }
+        } else {
+            keyboard::Event::KeyReleased {
+                key: key.clone(),
+                modified_key: key,
+                physical_key,
+                location,
+                modifiers,
+            }
+        })
+    }
+
+    fn char_event(c: &str, code: Code, modifiers: Modifiers, is_press: bool) -> Event {
+        key_event(
+            keyboard::Key::Character(c.into()),
+            code,
+            modifiers,
+            is_press,
+        )
+    }
+
+    fn a() -> MouseButtonOrKey {
+        MouseButtonOrKey::Key(Key::Unicode('a'))
+    }
+
+    fn left() -> MouseButtonOrKey {
+        MouseButtonOrKey::MouseButton(MouseButton::Left)
+    }
+
+    // Types `a`, then clicks the left mouse button, with 100, 200 and 50 ms between the events.
+    fn record_key_and_click(delays: RecordedDelays) -> Macro {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+        let mut recorder = MacroRecorder {
+            delays,
+            ..MacroRecorder::default()
+        };
+        recorder.start();
+        let none = Modifiers::default();
+        recorder.record(&char_event("a", Code::KeyA, none, true), start, false);
+        recorder.record(
+            &char_event("a", Code::KeyA, none, false),
+            start + ms(100),
+            false,
+        );
+        recorder.record(
+            &Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)),
+            start + ms(300),
+            false,
+        );
+        recorder.record(
+            &Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)),
+            start + ms(350),
+            false,
+        );
+        recorder.stop();
+        recorder.to_macro()
+    }
+
+    fn steps_with_waits(waits: [u64; 3]) -> Vec<MacroStep> {
+        vec![
+            MacroStep::KeyDown(Some(a())),
+            MacroStep::Wait(waits[0]),
+            MacroStep::KeyUp(Some(a())),
+            MacroStep::Wait(waits[1]),
+            MacroStep::KeyDown(Some(left())),
+            MacroStep::Wait(waits[2]),
+            MacroStep::KeyUp(Some(left())),
+        ]
+    }
+
+    #[test]
+    fn to_macro_as_recorded() {
+        let m = record_key_and_click(RecordedDelays::AsRecorded);
+        assert_eq!(m.steps, steps_with_waits([100, 200, 50]));
+    }
+
+    #[test]
+    fn to_macro_scaled() {
+        let m = record_key_and_click(RecordedDelays::Scaled(0.5));
+        assert_eq!(m.steps, steps_with_waits([50, 100, 25]));
+    }
+
+    #[test]
+    fn to_macro_fixed() {
+        let m = record_key_and_click(RecordedDelays::Fixed(20));
+        assert_eq!(m.steps, steps_with_waits([20, 20, 20]));
+
+        // Without any delay the waits are left out altogether.
+        let m = record_key_and_click(RecordedDelays::Fixed(0));
+        assert_eq!(
+            m.steps,
+            vec![
+                MacroStep::KeyDown(Some(a())),
+                MacroStep::KeyUp(Some(a())),
+                MacroStep::KeyDown(Some(left())),
+                MacroStep::KeyUp(Some(left())),
+            ]
+        );
+    }
+
+    #[test]
+    fn release_matches_press_by_physical_key() {
+        let now = Instant::now();
+        let mut recorder = MacroRecorder {
+            delays: RecordedDelays::Fixed(0),
+            ..MacroRecorder::default()
+        };
+        recorder.start();
+        let none = Modifiers::default();
+        let shift = Modifiers::SHIFT;
+        let shift_key = keyboard::Key::Named(Named::Shift);
+        recorder.record(&char_event("a", Code::KeyA, none, true), now, false);
+        recorder.record(
+            &key_event(shift_key.clone(), Code::ShiftLeft, none, true),
+            now,
+            false,
+        );
+        recorder.record(&char_event("A", Code::KeyA, shift, false), now, false);
+        recorder.record(
+            &key_event(shift_key, Code::ShiftLeft, shift, false),
+            now,
+            false,
+        );
+
+        let steps = recorder.to_macro().steps;
+        assert_eq!(steps.len(), 4);
+        assert_eq!(steps[0], MacroStep::KeyDown(Some(a())));
+        assert_eq!(steps[2], MacroStep::KeyUp(Some(a())));
+        let MacroStep::KeyDown(shift_output) = &steps[1] else {
+            panic!("Expected Shift to be pressed, got {:?}", steps[1]);
+        };
+        assert_eq!(steps[3], MacroStep::KeyUp(shift_output.clone()));
+    }
+
+    #[test]
+    fn to_macro_releases_keys_still_held() {
+        let now = Instant::now();
+        let mut recorder = MacroRecorder::default();
+        recorder.start();
+        recorder.record(
+            &char_event("a", Code::KeyA, Modifiers::default(), true),
+            now,
+            false,
+        );
+        recorder.stop();
+        assert_eq!(
+            recorder.to_macro().steps,
+            vec![MacroStep::KeyDown(Some(a())), MacroStep::KeyUp(Some(a()))]
+        );
+    }
+}
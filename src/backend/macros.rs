@@ -0,0 +1,114 @@
+use crate::backend::key_utils::MouseButtonOrKey;
+use enigo::{Button as MouseButton, Direction, Enigo, Keyboard, Mouse};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum MacroStep {
+    // Key steps can be added before a key has been assigned to them, and are skipped until then.
+    KeyDown(Option<MouseButtonOrKey>),
+    KeyUp(Option<MouseButtonOrKey>),
+    Click(MouseButton),
+    Text(String),
+    // In milliseconds.
+    Wait(u64),
+}
+
+impl Display for MacroStep {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MacroStep::KeyDown(_) => write!(f, "Key down"),
+            MacroStep::KeyUp(_) => write!(f, "Key up"),
+            MacroStep::Click(_) => write!(f, "Click"),
+            MacroStep::Text(_) => write!(f, "Type text"),
+            MacroStep::Wait(_) => write!(f, "Wait"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Macro {
+    pub steps: Vec<MacroStep>,
+    pub cancel_on_release: bool,
+    pub repeat_while_held: bool,
+}
+
+// Advanced a bit on every handler tick, so waiting never holds up the rest of the controller input.
+pub struct MacroRun {
+    macro_: Macro,
+    next_step: usize,
+    resume_at: Option<Instant>,
+    pressed: Vec<MouseButtonOrKey>,
+    is_held: bool,
+}
+
+impl MacroRun {
+    pub fn new(macro_: Macro) -> Self {
+        Self {
+            macro_,
+            next_step: 0,
+            resume_at: None,
+            pressed: Vec::new(),
+            is_held: true,
+        }
+    }
+
+    pub fn cancels_on_release(&self) -> bool {
+        self.macro_.cancel_on_release
+    }
+
+    pub fn release(&mut self) {
+        self.is_held = false;
+    }
+
+    // Plays every step that is due and returns whether the macro has finished.
+    pub fn advance(&mut self, enigo: &mut Enigo, now: Instant) -> Result<bool, String> {
+        while self.next_step < self.macro_.steps.len() {
+            if self.resume_at.is_some_and(|resume_at| now < resume_at) {
+                return Ok(false);
+            }
+            self.resume_at = None;
+
+            let step = self.macro_.steps[self.next_step].clone();
+            self.next_step += 1;
+            match step {
+                MacroStep::KeyDown(Some(output)) => {
+                    output.perform(enigo, Direction::Press)?;
+                    self.pressed.push(output);
+                }
+                MacroStep::KeyUp(Some(output)) => {
+                    output.perform(enigo, Direction::Release)?;
+                    self.pressed.retain(|pressed| *pressed != output);
+                }
+                MacroStep::KeyDown(None) | MacroStep::KeyUp(None) => {}
+                MacroStep::Click(mb) => {
+                    enigo
+                        .button(mb, Direction::Click)
+                        .map_err(|e| e.to_string())?;
+                }
+                MacroStep::Text(text) => enigo.text(&text).map_err(|e| e.to_string())?,
+                MacroStep::Wait(ms) => self.resume_at = Some(now + Duration::from_millis(ms)),
+            }
+        }
+
+        if self.macro_.repeat_while_held && self.is_held {
+            // The next round starts on the next tick, so a macro without waits can't lock up the
+            // handler.
+            self.next_step = 0;
+            return Ok(false);
+        }
+        self.cancel(enigo)?;
+        Ok(true)
+    }
+
+    pub fn cancel(&mut self, enigo: &mut Enigo) -> Result<(), String> {
+        self.next_step = self.macro_.steps.len();
+        let mut result = Ok(());
+        for output in self.pressed.drain(..).rev() {
+            result = result.and(output.perform(enigo, Direction::Release));
+        }
+        result
+    }
+}
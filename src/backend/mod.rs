@@ -1,4 +1,15 @@
+pub mod axis_binding;
+pub mod button_binding;
 pub mod config_manager;
 pub mod controller_handler;
+pub mod display_bounds;
+pub mod gamepad_matcher;
+pub mod held_outputs;
+pub mod input_source;
 pub mod joysticks;
 pub mod key_utils;
+pub mod launch_command;
+pub mod macro_recorder;
+pub mod macros;
+pub mod physical_keys;
+pub mod stick_config;
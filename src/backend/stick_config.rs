@@ -0,0 +1,637 @@
+use crate::backend::joysticks::StickDirection;
+use crate::backend::key_utils::MouseButtonOrKey;
+use enigo::Key;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub enum StickMode {
+    #[default]
+    Off,
+    Cursor,
+    Scroll,
+    Keys,
+}
+
+impl Display for StickMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StickMode::Off => write!(f, "Off"),
+            StickMode::Cursor => write!(f, "Cursor"),
+            StickMode::Scroll => write!(f, "Scroll"),
+            StickMode::Keys => write!(f, "Keys"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ScrollAxes {
+    #[default]
+    Both,
+    Vertical,
+    Horizontal,
+}
+
+impl ScrollAxes {
+    pub fn scrolls_vertically(&self) -> bool {
+        *self != ScrollAxes::Horizontal
+    }
+
+    pub fn scrolls_horizontally(&self) -> bool {
+        *self != ScrollAxes::Vertical
+    }
+}
+
+impl Display for ScrollAxes {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScrollAxes::Both => write!(f, "Vertical and horizontal"),
+            ScrollAxes::Vertical => write!(f, "Vertical only"),
+            ScrollAxes::Horizontal => write!(f, "Horizontal only"),
+        }
+    }
+}
+
+// X11 only knows whole scroll wheel notches, so both styles send notches and differ in their timing.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ScrollStyle {
+    #[default]
+    Smooth,
+    // Scrolls one notch as soon as the stick is tilted and then keeps stepping at a fixed rate, like
+    // a held arrow key.
+    Notches,
+}
+
+impl Display for ScrollStyle {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScrollStyle::Smooth => write!(f, "Smooth"),
+            ScrollStyle::Notches => write!(f, "Notches"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub enum KeyDirections {
+    // Only the direction the stick points at the most is held, like a D-pad without diagonals.
+    FourWay,
+    #[default]
+    EightWay,
+}
+
+impl Display for KeyDirections {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeyDirections::FourWay => write!(f, "4-way"),
+            KeyDirections::EightWay => write!(f, "8-way"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DirectionKeys {
+    pub up: Option<MouseButtonOrKey>,
+    pub down: Option<MouseButtonOrKey>,
+    pub left: Option<MouseButtonOrKey>,
+    pub right: Option<MouseButtonOrKey>,
+}
+
+impl Default for DirectionKeys {
+    fn default() -> Self {
+        Self::wasd()
+    }
+}
+
+impl DirectionKeys {
+    pub fn wasd() -> Self {
+        Self {
+            up: Some(MouseButtonOrKey::Key(Key::Unicode('w'))),
+            down: Some(MouseButtonOrKey::Key(Key::Unicode('s'))),
+            left: Some(MouseButtonOrKey::Key(Key::Unicode('a'))),
+            right: Some(MouseButtonOrKey::Key(Key::Unicode('d'))),
+        }
+    }
+
+    pub fn arrows() -> Self {
+        Self {
+            up: Some(MouseButtonOrKey::Key(Key::UpArrow)),
+            down: Some(MouseButtonOrKey::Key(Key::DownArrow)),
+            left: Some(MouseButtonOrKey::Key(Key::LeftArrow)),
+            right: Some(MouseButtonOrKey::Key(Key::RightArrow)),
+        }
+    }
+
+    pub fn get(&self, direction: StickDirection) -> Option<&MouseButtonOrKey> {
+        match direction {
+            StickDirection::Up => self.up.as_ref(),
+            StickDirection::Down => self.down.as_ref(),
+            StickDirection::Left => self.left.as_ref(),
+            StickDirection::Right => self.right.as_ref(),
+        }
+    }
+
+    pub fn set(&mut self, direction: StickDirection, output: Option<MouseButtonOrKey>) {
+        match direction {
+            StickDirection::Up => self.up = output,
+            StickDirection::Down => self.down = output,
+            StickDirection::Left => self.left = output,
+            StickDirection::Right => self.right = output,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub enum DeadzoneShape {
+    // Each axis is cut off on its own, which makes it easier to hold a perfectly straight line.
+    #[default]
+    Axial,
+    // The stick's distance from the center is cut off, which keeps diagonals smooth.
+    Radial,
+}
+
+impl Display for DeadzoneShape {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DeadzoneShape::Axial => write!(f, "Axial"),
+            DeadzoneShape::Radial => write!(f, "Radial"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum ResponseCurve {
+    #[default]
+    Linear,
+    Quadratic,
+    // Steepness of the curve, higher values leave more room for precise small movements.
+    Exponential(f32),
+    // Outputs at evenly spaced deflections ending at full tilt, with straight lines in between.
+    // A centered stick always maps to 0.0.
+    Custom(Vec<f32>),
+}
+
+impl ResponseCurve {
+    pub const DEFAULT_STEEPNESS: f32 = 3.0;
+    pub const CUSTOM_POINT_COUNT: usize = 8;
+
+    pub fn options() -> [ResponseCurve; 4] {
+        [
+            ResponseCurve::Linear,
+            ResponseCurve::Quadratic,
+            ResponseCurve::Exponential(Self::DEFAULT_STEEPNESS),
+            ResponseCurve::Custom(
+                (1..=Self::CUSTOM_POINT_COUNT)
+                    .map(|i| i as f32 / Self::CUSTOM_POINT_COUNT as f32)
+                    .collect(),
+            ),
+        ]
+    }
+
+    // Maps a deflection in 0.0..=1.0 to an output in 0.0..=1.0.
+    pub fn apply(&self, amt: f32) -> f32 {
+        let amt = amt.clamp(0.0, 1.0);
+        match self {
+            ResponseCurve::Linear => amt,
+            ResponseCurve::Quadratic => amt * amt,
+            ResponseCurve::Exponential(steepness) => {
+                if *steepness <= f32::EPSILON {
+                    amt
+                } else {
+                    (steepness * amt).exp_m1() / steepness.exp_m1()
+                }
+            }
+            ResponseCurve::Custom(points) => {
+                if points.is_empty() {
+                    return amt;
+                }
+                let position = amt * points.len() as f32;
+                let i = (position.floor() as usize).min(points.len() - 1);
+                let start = if i == 0 { 0.0 } else { points[i - 1] };
+                start + (points[i] - start) * (position - i as f32)
+            }
+        }
+    }
+}
+
+impl Display for ResponseCurve {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResponseCurve::Linear => write!(f, "Linear"),
+            ResponseCurve::Quadratic => write!(f, "Quadratic"),
+            ResponseCurve::Exponential(_) => write!(f, "Exponential"),
+            ResponseCurve::Custom(_) => write!(f, "Custom"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StickConfig {
+    pub mode: StickMode,
+    // Deflection below which the stick counts as centered.
+    pub inner_deadzone: f32,
+    // Deflection short of the edge at which the stick already counts as fully tilted.
+    pub outer_deadzone: f32,
+    pub deadzone_shape: DeadzoneShape,
+    pub sensitivity_x: f32,
+    pub sensitivity_y: f32,
+    pub response_curve: ResponseCurve,
+    // Extra speed gained per second the stick is held at full tilt, 0.0 turns acceleration off.
+    pub acceleration: f32,
+    pub max_acceleration: f32,
+    // Sends the cursor movement as relative motion rather than absolute positions, which is what
+    // games that lock and hide the pointer listen to.
+    pub relative_mouse: bool,
+    pub scroll_axes: ScrollAxes,
+    pub scroll_style: ScrollStyle,
+    // Notches scrolled per second, at full tilt for smooth scrolling.
+    pub scroll_speed: f32,
+    pub key_directions: KeyDirections,
+    pub direction_keys: DirectionKeys,
+    pub key_press_threshold: f32,
+    // Keeping this below the press threshold stops keys from chattering when the stick rests near
+    // it.
+    pub key_release_threshold: f32,
+}
+
+impl Default for StickConfig {
+    fn default() -> Self {
+        Self {
+            mode: StickMode::default(),
+            inner_deadzone: 0.05,
+            outer_deadzone: 0.0,
+            deadzone_shape: DeadzoneShape::default(),
+            sensitivity_x: 1.0,
+            sensitivity_y: 1.0,
+            response_curve: ResponseCurve::default(),
+            acceleration: 0.0,
+            max_acceleration: 2.0,
+            relative_mouse: false,
+            scroll_axes: ScrollAxes::default(),
+            scroll_style: ScrollStyle::default(),
+            scroll_speed: 10.0,
+            key_directions: KeyDirections::default(),
+            direction_keys: DirectionKeys::default(),
+            key_press_threshold: 0.5,
+            key_release_threshold: 0.35,
+        }
+    }
+}
+
+impl StickConfig {
+    pub const MAX_INNER_DEADZONE: f32 = 0.5;
+    pub const MAX_OUTER_DEADZONE: f32 = 0.4;
+    pub const MAX_SENSITIVITY: f32 = 5.0;
+    pub const MAX_ACCELERATION: f32 = 5.0;
+    pub const MAX_SCROLL_SPEED: f32 = 40.0;
+    // Processed deflection from which the stick counts as fully tilted for acceleration.
+    pub const FULL_TILT: f32 = 0.99;
+
+    // Maps the raw stick position to one where both deadzones have been cut off and the remaining
+    // range has been stretched back out to 0.0..=1.0.
+    pub fn apply(&self, x: f32, y: f32) -> (f32, f32) {
+        self.map_deflection(x, y, |amt| self.rescale(amt))
+    }
+
+    // Bends a position from apply along the response curve. Only the cursor and scroll speeds follow
+    // the curve, the key thresholds are compared against the position from apply.
+    pub fn apply_response_curve(&self, x: f32, y: f32) -> (f32, f32) {
+        self.map_deflection(x, y, |amt| {
+            self.response_curve.apply(amt.abs()).copysign(amt)
+        })
+    }
+
+    // Radial deadzones map the distance from the center rather than each axis, so the direction is
+    // kept.
+    fn map_deflection(&self, x: f32, y: f32, map: impl Fn(f32) -> f32) -> (f32, f32) {
+        match self.deadzone_shape {
+            DeadzoneShape::Axial => (map(x), map(y)),
+            DeadzoneShape::Radial => {
+                let magnitude = x.hypot(y);
+                if magnitude == 0.0 {
+                    return (0.0, 0.0);
+                }
+                let scale = map(magnitude) / magnitude;
+                (x * scale, y * scale)
+            }
+        }
+    }
+
+    fn rescale(&self, amt: f32) -> f32 {
+        let inner = self.inner_deadzone.clamp(0.0, Self::MAX_INNER_DEADZONE);
+        let outer = self.outer_deadzone.clamp(0.0, Self::MAX_OUTER_DEADZONE);
+        if amt.abs() <= inner {
+            return 0.0;
+        }
+        let scaled = (amt.abs() - inner) / (1.0 - outer - inner);
+        scaled.min(1.0).copysign(amt)
+    }
+
+    pub fn get_key_directions(
+        &self,
+        x: f32,
+        y: f32,
+        held: &[StickDirection],
+    ) -> Vec<StickDirection> {
+        let press = self.key_press_threshold;
+        let release = self.key_release_threshold.min(press);
+        let mut active: Vec<(StickDirection, f32)> = StickDirection::ALL
+            .into_iter()
+            .map(|direction| (direction, direction.get_component(x, y)))
+            .filter(|(direction, amt)| {
+                *amt >= press || (held.contains(direction) && *amt > release)
+            })
+            .collect();
+
+        if self.key_directions == KeyDirections::FourWay {
+            // Near a diagonal the held direction has to be beaten by the same margin as the
+            // thresholds, otherwise the keys would flicker between the two neighbours.
+            active.sort_by(|(a_dir, a_amt), (b_dir, b_amt)| {
+                let a = a_amt
+                    + if held.contains(a_dir) {
+                        press - release
+                    } else {
+                        0.0
+                    };
+                let b = b_amt
+                    + if held.contains(b_dir) {
+                        press - release
+                    } else {
+                        0.0
+                    };
+                b.total_cmp(&a)
+            });
+            active.truncate(1);
+        }
+
+        active.into_iter().map(|(direction, _)| direction).collect()
+    }
+
+    pub fn get_acceleration_multiplier(&self, full_tilt_secs: f32) -> f32 {
+        (1.0 + self.acceleration * full_tilt_secs).min(self.max_acceleration.max(1.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-5,
+            "Expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    fn deadzones(inner: f32, outer: f32, shape: DeadzoneShape) -> StickConfig {
+        StickConfig {
+            inner_deadzone: inner,
+            outer_deadzone: outer,
+            deadzone_shape: shape,
+            ..StickConfig::default()
+        }
+    }
+
+    #[test]
+    fn apply_cuts_off_inner_deadzone() {
+        let sc = deadzones(0.1, 0.0, DeadzoneShape::Axial);
+        assert_eq!(sc.apply(0.1, -0.1), (0.0, 0.0));
+        assert_eq!(sc.apply(0.05, 0.0), (0.0, 0.0));
+
+        // Just past the edge the output starts from 0.0 rather than jumping to the deadzone.
+        let (x, _) = sc.apply(0.11, 0.0);
+        assert!(x > 0.0 && x < 0.02);
+    }
+
+    #[test]
+    fn apply_stretches_remaining_range() {
+        let sc = deadzones(0.1, 0.1, DeadzoneShape::Axial);
+        let (x, y) = sc.apply(0.5, -0.5);
+        assert_close(x, 0.5);
+        assert_close(y, -0.5);
+    }
+
+    #[test]
+    fn apply_clamps_at_outer_deadzone() {
+        let sc = deadzones(0.1, 0.1, DeadzoneShape::Axial);
+        for raw in [0.9, 0.95, 1.0] {
+            let (x, y) = sc.apply(raw, -raw);
+            assert_close(x, 1.0);
+            assert_close(y, -1.0);
+        }
+    }
+
+    #[test]
+    fn axial_deadzone_cuts_each_axis() {
+        let sc = deadzones(0.1, 0.0, DeadzoneShape::Axial);
+        // Both axes are inside the deadzone, even though the stick as a whole is outside of it.
+        assert_eq!(sc.apply(0.08, 0.08), (0.0, 0.0));
+
+        let (_, y) = sc.apply(0.5, 0.05);
+        assert_eq!(y, 0.0);
+    }
+
+    #[test]
+    fn radial_deadzone_keeps_direction() {
+        let sc = deadzones(0.1, 0.0, DeadzoneShape::Radial);
+        let (x, y) = sc.apply(0.08, 0.08);
+        assert!(x > 0.0);
+        assert_close(x, y);
+
+        let (x, y) = sc.apply(0.5, 0.05);
+        assert_close(y / x, 0.1);
+
+        assert_eq!(sc.apply(0.05, 0.05), (0.0, 0.0));
+        assert_eq!(sc.apply(0.0, 0.0), (0.0, 0.0));
+    }
+
+    #[test]
+    fn radial_deadzone_clamps_magnitude() {
+        let sc = deadzones(0.1, 0.1, DeadzoneShape::Radial);
+        let (x, y) = sc.apply(0.8, 0.8);
+        assert_close(x.hypot(y), 1.0);
+        assert_close(x, y);
+    }
+
+    #[test]
+    fn response_curves_keep_endpoints() {
+        for curve in ResponseCurve::options() {
+            assert_close(curve.apply(0.0), 0.0);
+            assert_close(curve.apply(1.0), 1.0);
+            // Out of range deflections are clamped first.
+            assert_close(curve.apply(1.5), 1.0);
+        }
+    }
+
+    #[test]
+    fn apply_leaves_out_response_curve() {
+        let sc = StickConfig {
+            response_curve: ResponseCurve::Quadratic,
+            ..deadzones(0.0, 0.0, DeadzoneShape::Axial)
+        };
+        assert_eq!(sc.apply(0.5, -0.5), (0.5, -0.5));
+        let (x, y) = sc.apply_response_curve(0.5, -0.5);
+        assert_close(x, 0.25);
+        assert_close(y, -0.25);
+    }
+
+    #[test]
+    fn radial_response_curve_keeps_direction() {
+        let sc = StickConfig {
+            response_curve: ResponseCurve::Quadratic,
+            ..deadzones(0.0, 0.0, DeadzoneShape::Radial)
+        };
+        let (x, y) = sc.apply_response_curve(0.3, 0.4);
+        assert_close(x.hypot(y), 0.25);
+        assert_close(y / x, 0.4 / 0.3);
+    }
+
+    #[test]
+    fn response_curves_shape() {
+        assert_close(ResponseCurve::Linear.apply(0.5), 0.5);
+        assert_close(ResponseCurve::Quadratic.apply(0.5), 0.25);
+        assert!(ResponseCurve::Exponential(3.0).apply(0.5) < 0.25);
+        assert_close(ResponseCurve::Exponential(0.0).apply(0.5), 0.5);
+    }
+
+    #[test]
+    fn custom_curve_interpolates_points() {
+        let curve = ResponseCurve::Custom(vec![0.1, 0.2, 0.5, 1.0]);
+        assert_close(curve.apply(0.125), 0.05);
+        assert_close(curve.apply(0.25), 0.1);
+        assert_close(curve.apply(0.625), 0.35);
+        assert_close(curve.apply(1.0), 1.0);
+
+        // The default points make a straight line.
+        let ResponseCurve::Custom(points) = &ResponseCurve::options()[3] else {
+            panic!("Expected the last option to be a custom curve");
+        };
+        let linear = ResponseCurve::Custom(points.clone());
+        for i in 0..=20 {
+            let amt = i as f32 / 20.0;
+            assert_close(linear.apply(amt), amt);
+        }
+    }
+
+    #[test]
+    fn custom_curve_with_monotonic_points_is_monotonic() {
+        let curve = ResponseCurve::Custom(vec![0.0, 0.05, 0.1, 0.3, 0.3, 0.6, 0.9, 1.0]);
+        let mut last = 0.0;
+        for i in 0..=1000 {
+            let out = curve.apply(i as f32 / 1000.0);
+            assert!(out >= last, "{} dropped below {}", out, last);
+            last = out;
+        }
+    }
+
+    fn keys(key_directions: KeyDirections) -> StickConfig {
+        StickConfig {
+            key_directions,
+            key_press_threshold: 0.5,
+            key_release_threshold: 0.35,
+            ..StickConfig::default()
+        }
+    }
+
+    #[test]
+    fn key_directions_press_at_threshold() {
+        let sc = keys(KeyDirections::EightWay);
+        assert!(sc.get_key_directions(0.0, 0.49, &[]).is_empty());
+        assert_eq!(
+            sc.get_key_directions(0.0, 0.5, &[]),
+            vec![StickDirection::Up]
+        );
+        assert_eq!(
+            sc.get_key_directions(-0.7, 0.0, &[]),
+            vec![StickDirection::Left]
+        );
+    }
+
+    #[test]
+    fn key_directions_release_below_release_threshold() {
+        let sc = keys(KeyDirections::EightWay);
+        let held = [StickDirection::Up];
+        assert_eq!(
+            sc.get_key_directions(0.0, 0.4, &held),
+            vec![StickDirection::Up]
+        );
+        assert!(sc.get_key_directions(0.0, 0.35, &held).is_empty());
+        // The same position doesn't press a key that isn't held yet.
+        assert!(sc.get_key_directions(0.0, 0.4, &[]).is_empty());
+    }
+
+    #[test]
+    fn key_directions_release_threshold_is_capped_at_press_threshold() {
+        let sc = StickConfig {
+            key_release_threshold: 0.9,
+            ..keys(KeyDirections::EightWay)
+        };
+        let held = [StickDirection::Down];
+        assert_eq!(
+            sc.get_key_directions(0.0, -0.6, &held),
+            vec![StickDirection::Down]
+        );
+        assert!(sc.get_key_directions(0.0, -0.45, &held).is_empty());
+    }
+
+    #[test]
+    fn eight_way_holds_both_keys_at_diagonals() {
+        let sc = keys(KeyDirections::EightWay);
+        assert_eq!(
+            sc.get_key_directions(0.6, 0.6, &[]),
+            vec![StickDirection::Up, StickDirection::Right]
+        );
+        assert_eq!(
+            sc.get_key_directions(-0.6, -0.6, &[]),
+            vec![StickDirection::Down, StickDirection::Left]
+        );
+    }
+
+    #[test]
+    fn four_way_picks_strongest_direction() {
+        let sc = keys(KeyDirections::FourWay);
+        assert_eq!(
+            sc.get_key_directions(0.6, 0.7, &[]),
+            vec![StickDirection::Up]
+        );
+        assert_eq!(
+            sc.get_key_directions(0.7, 0.6, &[]),
+            vec![StickDirection::Right]
+        );
+    }
+
+    #[test]
+    fn key_directions_ignore_response_curve() {
+        let sc = StickConfig {
+            inner_deadzone: 0.0,
+            response_curve: ResponseCurve::Quadratic,
+            ..keys(KeyDirections::EightWay)
+        };
+        // Through the curve 0.6 would drop under the press threshold and 0.4 under the release one.
+        let (x, y) = sc.apply(0.0, 0.6);
+        assert_eq!(sc.get_key_directions(x, y, &[]), vec![StickDirection::Up]);
+        let (x, y) = sc.apply(0.0, 0.4);
+        assert_eq!(
+            sc.get_key_directions(x, y, &[StickDirection::Up]),
+            vec![StickDirection::Up]
+        );
+    }
+
+    #[test]
+    fn four_way_held_direction_is_biased_at_diagonals() {
+        let sc = keys(KeyDirections::FourWay);
+        let held = [StickDirection::Up];
+        // Right leads, but not by the 0.15 between the thresholds.
+        assert_eq!(
+            sc.get_key_directions(0.7, 0.6, &held),
+            vec![StickDirection::Up]
+        );
+        assert_eq!(
+            sc.get_key_directions(0.8, 0.6, &held),
+            vec![StickDirection::Right]
+        );
+    }
+}
@@ -1,6 +1,5 @@
 mod backend;
 mod ui;
-mod utils;
 
 use crate::ui::application::Application;
 
@@ -1,41 +1,105 @@
-use crate::backend::config_manager::ProfileConfig;
-use crate::backend::controller_handler::handle_controller_input;
+use crate::backend::axis_binding::{AxisBinding, AxisHalf};
+use crate::backend::button_binding::ButtonBinding;
+use crate::backend::config_manager::{GamepadConfig, ProfileConfig};
+use crate::backend::controller_handler::{HandlerStatus, handle_controller_input};
+use crate::backend::display_bounds::EdgeBehavior;
+use crate::backend::gamepad_matcher::{ConnectedGamepad, GamepadMatcher, get_connected_gamepads};
+use crate::backend::input_source::OutputSlot;
 use crate::backend::joysticks::Joystick;
+use crate::backend::key_utils::{CapturedKey, MouseButtonOrKey};
+use crate::backend::launch_command::LaunchCommand;
+use crate::backend::macro_recorder::{MacroRecorder, RecordedDelays};
+use crate::backend::macros::{Macro, MacroStep};
+use crate::backend::stick_config::StickConfig;
+use crate::ui::handler_state::{HandlerActivity, HandlerState};
 use crate::ui::window::base::{Window, WindowType};
 use crate::ui::window::error_window::ErrorWindow;
 use crate::ui::window::key_press_window::KeyPressWindow;
+use crate::ui::window::macro_editor_window::MacroEditorWindow;
+use crate::ui::window::macro_recorder_window::MacroRecorderWindow;
 use crate::ui::window::main_window::MainWindow;
-use anyhow::Result;
 use enigo::Button as MouseButton;
-use gilrs::Button;
+use gilrs::{Axis, Button};
+use iced::futures::channel::mpsc::{UnboundedSender, unbounded};
+use iced::futures::{SinkExt, Stream, StreamExt};
 use iced::widget::text;
 use iced::window::{Id, Settings};
-use iced::{Element, Event, Size, Subscription, Task, Vector, keyboard, window};
+use iced::{Element, Event, Size, Subscription, Task, Vector, event, keyboard, mouse, window};
 use std::collections::BTreeMap;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{Sender, channel};
 use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Instant;
 
 #[derive(Clone, Debug)]
 pub enum Message {
     Activate,
-    Activated(Result<(), String>),
     Deactivate,
-    ToggleAxisSelection(Joystick),
-    OpenKeySetWindow(Button),
+    HandlerStatusChannelReady(UnboundedSender<HandlerStatus>),
+    HandlerStatusChanged(HandlerStatus),
+    SelectGamepadConfig(usize),
+    AddGamepadConfig,
+    DuplicateGamepadConfig,
+    RemoveGamepadConfig,
+    SetGamepadMatcher(GamepadMatcher),
+    SetFallbackGamepadConfig(bool),
+    SetEdgeBehavior(EdgeBehavior),
+    RefreshConnectedGamepads,
+    SetStickConfig(Joystick, StickConfig),
+    OpenKeySetWindow(OutputSlot),
     WindowOpened(Id, WindowType),
     WindowClosed(Id),
-    KeyPressed(keyboard::Key),
-    MouseButtonSet(Button, MouseButton),
-    UnsetButton(Button),
+    KeyPressed(CapturedKey),
+    KeyReleased(CapturedKey),
+    SetBindPhysicalKeys(bool),
+    MouseButtonSet(OutputSlot, MouseButton),
+    UnsetButton(OutputSlot),
+    SetTypedText(OutputSlot, String),
+    SetLaunchCommand(OutputSlot, LaunchCommand),
+    SetButtonBinding(Button, ButtonBinding),
+    AddAxisBinding(AxisBinding),
+    SetAxisBinding(AxisBinding),
+    RemoveAxisBinding(Axis, AxisHalf),
+    OpenMacroEditorWindow(OutputSlot),
+    // The gamepad config index and output slot the macro belongs to, and the edited macro.
+    SetMacro(usize, OutputSlot, Macro),
+    OpenMacroStepKeySetWindow(usize, OutputSlot, usize),
+    OpenMacroRecorderWindow(OutputSlot),
+    StartMacroRecording,
+    StopMacroRecording,
+    RecordEvent(Event, Instant),
+    SetRecordedDelays(RecordedDelays),
+    SaveRecordedMacro(usize, OutputSlot),
     SaveProfile,
     LoadProfile,
 }
 
+// What the key press window assigns the captured key to. The gamepad config index is remembered
+// too, in case the selection changes while binding.
+#[derive(Clone, Copy)]
+enum KeyBindTarget {
+    Output(usize, OutputSlot),
+    MacroStep(usize, OutputSlot, usize),
+}
+
 pub struct Application {
-    current_btn_to_bind: Option<Button>,
+    current_key_bind_target: Option<KeyBindTarget>,
+    key_press_error: Arc<Mutex<Option<String>>>,
+    // Modifiers held down in the key press window so far, in the order they were pressed.
+    pending_modifier_keys: Vec<CapturedKey>,
+    bind_physical_keys: Arc<AtomicBool>,
+    macro_window_target: Option<(usize, OutputSlot)>,
+    macro_recorder: Arc<Mutex<MacroRecorder>>,
     profile_config: Arc<Mutex<ProfileConfig>>,
+    selected_gamepad_config: Arc<AtomicUsize>,
+    connected_gamepads: Arc<Mutex<Vec<ConnectedGamepad>>>,
     windows: BTreeMap<Id, Box<dyn Window>>,
     is_handler_running: Arc<AtomicBool>,
+    handler_state: Arc<Mutex<HandlerState>>,
+    // Handed to every handler thread so it can report back through handler_status_worker.
+    handler_status_sender: Option<UnboundedSender<HandlerStatus>>,
+    handler_profile_sender: Option<Sender<ProfileConfig>>,
     current_error: String,
 }
 
@@ -45,22 +109,131 @@ impl Application {
 
         (
             Self {
-                current_btn_to_bind: None,
+                current_key_bind_target: None,
+                macro_window_target: None,
+                macro_recorder: Arc::new(Mutex::new(MacroRecorder::default())),
+                key_press_error: Arc::new(Mutex::new(None)),
+                pending_modifier_keys: Vec::new(),
+                bind_physical_keys: Arc::new(AtomicBool::new(false)),
                 profile_config: Arc::new(Mutex::new(ProfileConfig::default())),
+                selected_gamepad_config: Arc::new(AtomicUsize::new(0)),
+                connected_gamepads: Arc::new(Mutex::new(Vec::new())),
                 windows: BTreeMap::new(),
                 is_handler_running: Arc::new(AtomicBool::new(false)),
+                handler_state: Arc::new(Mutex::new(HandlerState::default())),
+                handler_status_sender: None,
+                handler_profile_sender: None,
                 current_error: String::new(),
             },
-            open.map(|id| Message::WindowOpened(id, WindowType::Main)),
+            Task::batch([
+                open.map(|id| Message::WindowOpened(id, WindowType::Main)),
+                Task::done(Message::RefreshConnectedGamepads),
+            ]),
         )
     }
 
+    fn selected_gamepad_config(&self) -> usize {
+        self.selected_gamepad_config.load(Ordering::Relaxed)
+    }
+
+    fn edit_gamepad_config(&self, index: usize, edit: impl FnOnce(&mut GamepadConfig)) {
+        {
+            let mut profile_config = self.profile_config.lock().unwrap();
+            if let Some(gc) = profile_config.get_gamepad_config_mut(index) {
+                edit(gc);
+            }
+        }
+        self.push_profile_to_handler();
+    }
+
+    fn push_profile_to_handler(&self) {
+        if let Some(sender) = &self.handler_profile_sender {
+            // Sending only fails if the handler has already stopped, in which case there's nothing to update.
+            let _ = sender.send(self.profile_config.lock().unwrap().clone());
+        }
+    }
+
     fn is_key_press_window_open(&self) -> bool {
         self.windows
             .values()
             .any(|window| window.window_type() == WindowType::KeyPress)
     }
 
+    fn bind_captured_output(&mut self, output: MouseButtonOrKey) -> Task<Message> {
+        match self.current_key_bind_target {
+            Some(KeyBindTarget::Output(index, slot)) => {
+                self.edit_gamepad_config(index, |gc| gc.insert_output(slot, output))
+            }
+            Some(KeyBindTarget::MacroStep(index, slot, step)) => {
+                self.edit_gamepad_config(index, |gc| {
+                    let Some(MouseButtonOrKey::Macro(m)) = gc.get_key(&slot) else {
+                        return;
+                    };
+                    let mut m = m.clone();
+                    if let Some(MacroStep::KeyDown(key) | MacroStep::KeyUp(key)) =
+                        m.steps.get_mut(step)
+                    {
+                        *key = Some(output);
+                        gc.insert_output(slot, MouseButtonOrKey::Macro(m));
+                    }
+                })
+            }
+            None => {}
+        }
+        let key_press_window = self
+            .windows
+            .iter()
+            .find(|(_, window)| window.window_type() == WindowType::KeyPress);
+        if let Some((id, _)) = key_press_window {
+            return window::close(*id);
+        }
+        Task::none()
+    }
+
+    fn show_key_press_error(&self, captured: &CapturedKey) -> Task<Message> {
+        // The window stays open so a different key can be tried.
+        *self.key_press_error.lock().unwrap() = Some(format!(
+            "{} can't be assigned, please press a different key.",
+            captured.describe()
+        ));
+        Task::none()
+    }
+
+    fn open_key_set_window(&mut self, target: KeyBindTarget) -> Task<Message> {
+        self.current_key_bind_target = Some(target);
+        *self.key_press_error.lock().unwrap() = None;
+        self.pending_modifier_keys.clear();
+
+        self.open_window_near_last(
+            Settings {
+                max_size: Some(Size::new(400_f32, 200_f32)),
+                ..Settings::default()
+            },
+            WindowType::KeyPress,
+        )
+    }
+
+    fn open_window_near_last(&self, settings: Settings, window_type: WindowType) -> Task<Message> {
+        let Some(last_window) = self.windows.keys().last() else {
+            return Task::none();
+        };
+
+        window::position(*last_window)
+            .then(move |last_position| {
+                let position = last_position.map_or(window::Position::Default, |last_position| {
+                    window::Position::Specific(last_position + Vector::new(20.0, 20.0))
+                });
+
+                let (_, open) = window::open(Settings {
+                    position,
+                    ..settings.clone()
+                });
+
+                open
+            })
+            .map(move |id| Message::WindowOpened(id, window_type.clone()))
+    }
+
     fn handle_error(&mut self, err: String) -> Task<Message> {
         self.current_error = err;
         let (_, open_task) = window::open(Settings::default());
@@ -69,57 +242,157 @@ impl Application {
 
     pub fn update(&mut self, message: Message) -> Task<Message> {
         match message {
-            Message::Activate => Task::perform(
-                {
-                    self.is_handler_running.store(true, Ordering::Relaxed);
-                    handle_controller_input(
-                        self.profile_config.clone(),
-                        self.is_handler_running.clone(),
-                    )
-                },
-                Message::Activated,
-            ),
-            Message::Activated(result) => {
-                if let Err(e) = result {
+            Message::Activate => {
+                let Some(status_sender) = self.handler_status_sender.clone() else {
+                    return self.handle_error(
+                        "ERROR: The controller handler isn't ready yet, please try again!"
+                            .to_string(),
+                    );
+                };
+                if self.handler_state.lock().unwrap().activity != HandlerActivity::Inactive {
+                    return Task::none();
+                }
+
+                let (profile_sender, profile_receiver) = channel();
+                let profile_config = self.profile_config.lock().unwrap().clone();
+                let is_handler_running = self.is_handler_running.clone();
+                is_handler_running.store(true, Ordering::Relaxed);
+
+                let spawn_result = thread::Builder::new()
+                    .name("controller-handler".to_string())
+                    .spawn(move || {
+                        handle_controller_input(
+                            profile_config,
+                            profile_receiver,
+                            is_handler_running,
+                            status_sender,
+                        )
+                    });
+                if let Err(e) = spawn_result {
                     self.is_handler_running.store(false, Ordering::Relaxed);
-                    return self.handle_error(e);
+                    return self.handle_error(e.to_string());
                 }
+
+                self.handler_profile_sender = Some(profile_sender);
+                self.handler_state.lock().unwrap().activity = HandlerActivity::Starting;
                 Task::none()
             }
             Message::Deactivate => {
                 self.is_handler_running.store(false, Ordering::Relaxed);
+                let mut handler_state = self.handler_state.lock().unwrap();
+                if handler_state.activity != HandlerActivity::Inactive {
+                    handler_state.activity = HandlerActivity::Stopping;
+                }
                 Task::none()
             }
-            Message::ToggleAxisSelection(js) => {
-                let mut profile_config = self.profile_config.lock().unwrap();
-                profile_config.toggle_axis_all(js);
-
+            Message::HandlerStatusChannelReady(sender) => {
+                self.handler_status_sender = Some(sender);
                 Task::none()
             }
-            Message::OpenKeySetWindow(btn) => {
-                let Some(last_window) = self.windows.keys().last() else {
-                    return Task::none();
-                };
-
-                self.current_btn_to_bind = Some(btn);
-
-                window::position(*last_window)
-                    .then(|last_position| {
-                        let position =
-                            last_position.map_or(window::Position::Default, |last_position| {
-                                window::Position::Specific(last_position + Vector::new(20.0, 20.0))
-                            });
-
-                        let (_, open) = window::open(Settings {
-                            position,
-                            max_size: Some(Size::new(400_f32, 200_f32)),
-                            ..Settings::default()
-                        });
+            Message::HandlerStatusChanged(status) => {
+                {
+                    let mut handler_state = self.handler_state.lock().unwrap();
+                    match &status {
+                        // A handler that was deactivated while starting up is stopping already.
+                        HandlerStatus::Started
+                            if handler_state.activity == HandlerActivity::Starting =>
+                        {
+                            handler_state.activity = HandlerActivity::Active
+                        }
+                        HandlerStatus::Stopped => {
+                            handler_state.activity = HandlerActivity::Inactive;
+                            handler_state.latched_buttons.clear();
+                        }
+                        HandlerStatus::LatchedButtonsChanged(latched) => {
+                            handler_state.latched_buttons = latched.clone()
+                        }
+                        _ => {}
+                    }
+                    handler_state.last_status = Some(status.clone());
+                }
 
-                        open
-                    })
-                    .map(|id| Message::WindowOpened(id, WindowType::KeyPress))
+                match status {
+                    HandlerStatus::ControllerConnected(_)
+                    | HandlerStatus::ControllerDisconnected(_) => {
+                        Task::done(Message::RefreshConnectedGamepads)
+                    }
+                    HandlerStatus::Error(e) | HandlerStatus::CommandFailed(e) => {
+                        self.handle_error(e)
+                    }
+                    HandlerStatus::Stopped => {
+                        self.handler_profile_sender = None;
+                        Task::none()
+                    }
+                    _ => Task::none(),
+                }
+            }
+            Message::SelectGamepadConfig(index) => {
+                self.selected_gamepad_config.store(index, Ordering::Relaxed);
+                Task::none()
+            }
+            Message::AddGamepadConfig => {
+                let index = self.profile_config.lock().unwrap().add_gamepad_config();
+                self.selected_gamepad_config.store(index, Ordering::Relaxed);
+                self.push_profile_to_handler();
+                Task::none()
+            }
+            Message::DuplicateGamepadConfig => {
+                let duplicated = self
+                    .profile_config
+                    .lock()
+                    .unwrap()
+                    .duplicate_gamepad_config(self.selected_gamepad_config());
+                if let Some(index) = duplicated {
+                    self.selected_gamepad_config.store(index, Ordering::Relaxed);
+                }
+                self.push_profile_to_handler();
+                Task::none()
+            }
+            Message::RemoveGamepadConfig => {
+                {
+                    let mut profile_config = self.profile_config.lock().unwrap();
+                    let selected = self.selected_gamepad_config();
+                    profile_config.remove_gamepad_config(selected);
+                    self.selected_gamepad_config.store(
+                        selected.min(profile_config.gamepad_config_count() - 1),
+                        Ordering::Relaxed,
+                    );
+                }
+                self.push_profile_to_handler();
+                Task::none()
+            }
+            Message::SetGamepadMatcher(matcher) => {
+                self.edit_gamepad_config(self.selected_gamepad_config(), |gc| gc.matcher = matcher);
+                Task::none()
             }
+            Message::SetFallbackGamepadConfig(is_fallback) => {
+                self.profile_config
+                    .lock()
+                    .unwrap()
+                    .set_fallback_gamepad_config(self.selected_gamepad_config(), is_fallback);
+                self.push_profile_to_handler();
+                Task::none()
+            }
+            Message::SetEdgeBehavior(edge_behavior) => {
+                self.profile_config.lock().unwrap().edge_behavior = edge_behavior;
+                self.push_profile_to_handler();
+                Task::none()
+            }
+            Message::RefreshConnectedGamepads => match get_connected_gamepads() {
+                Ok(connected_gamepads) => {
+                    *self.connected_gamepads.lock().unwrap() = connected_gamepads;
+                    Task::none()
+                }
+                Err(e) => self.handle_error(e.to_string()),
+            },
+            Message::SetStickConfig(js, sc) => {
+                self.edit_gamepad_config(self.selected_gamepad_config(), |gc| {
+                    gc.set_stick_config(js, sc)
+                });
+                Task::none()
+            }
+            Message::OpenKeySetWindow(slot) => self
+                .open_key_set_window(KeyBindTarget::Output(self.selected_gamepad_config(), slot)),
             Message::WindowOpened(id, window_type) => {
                 match window_type {
                     WindowType::Main => {
@@ -127,12 +400,45 @@ impl Application {
                             id,
                             Box::new(MainWindow::new(
                                 self.profile_config.clone(),
-                                self.is_handler_running.clone(),
+                                self.handler_state.clone(),
+                                self.selected_gamepad_config.clone(),
+                                self.connected_gamepads.clone(),
                             )),
                         );
                     }
                     WindowType::KeyPress => {
-                        self.windows.insert(id, Box::new(KeyPressWindow {}));
+                        self.windows.insert(
+                            id,
+                            Box::new(KeyPressWindow::new(
+                                self.key_press_error.clone(),
+                                self.bind_physical_keys.clone(),
+                            )),
+                        );
+                    }
+                    WindowType::MacroEditor => {
+                        if let Some((index, slot)) = self.macro_window_target {
+                            self.windows.insert(
+                                id,
+                                Box::new(MacroEditorWindow::new(
+                                    self.profile_config.clone(),
+                                    index,
+                                    slot,
+                                )),
+                            );
+                        }
+                    }
+                    WindowType::MacroRecorder => {
+                        if let Some((index, slot)) = self.macro_window_target {
+                            self.windows.insert(
+                                id,
+                                Box::new(MacroRecorderWindow::new(
+                                    self.macro_recorder.clone(),
+                                    self.bind_physical_keys.clone(),
+                                    index,
+                                    slot,
+                                )),
+                            );
+                        }
                     }
                     WindowType::Error => {
                         self.windows
@@ -143,7 +449,10 @@ impl Application {
                 Task::none()
             }
             Message::WindowClosed(id) => {
-                self.windows.remove(&id);
+                let closed = self.windows.remove(&id);
+                if closed.is_some_and(|window| window.window_type() == WindowType::MacroRecorder) {
+                    self.macro_recorder.lock().unwrap().stop();
+                }
 
                 if self.windows.is_empty() {
                     iced::exit()
@@ -151,30 +460,153 @@ impl Application {
                     Task::none()
                 }
             }
-            Message::KeyPressed(key) => {
-                if key != keyboard::Key::Unidentified {
-                    let mut profile_config = self.profile_config.lock().unwrap();
-                    // TODO: Replace self.current_btn_to_bind.unwrap() with a proper check
-                    profile_config.insert_key_to_all(self.current_btn_to_bind.unwrap(), key);
-                    let key_press_window = self
-                        .windows
+            Message::KeyPressed(captured) => {
+                if captured.is_modifier() {
+                    // Wait for the key the modifiers belong to, or for them to be released alone.
+                    if !self
+                        .pending_modifier_keys
                         .iter()
-                        .find(|(_, window)| window.window_type() == WindowType::KeyPress);
-                    if let Some((id, _)) = key_press_window {
-                        return window::close(*id);
+                        .any(|pending| pending.physical_key == captured.physical_key)
+                    {
+                        self.pending_modifier_keys.push(captured);
                     }
+                    return Task::none();
+                }
+
+                self.pending_modifier_keys.clear();
+                match captured.to_combo(self.bind_physical_keys.load(Ordering::Relaxed)) {
+                    Some(output) => self.bind_captured_output(output),
+                    None => self.show_key_press_error(&captured),
                 }
-                Task::none()
             }
-            Message::MouseButtonSet(btn, mb) => {
-                let mut profile_config = self.profile_config.lock().unwrap();
-                profile_config.insert_mouse_button_to_all(btn, mb);
+            Message::KeyReleased(captured) => {
+                if !captured.is_modifier() || self.pending_modifier_keys.is_empty() {
+                    return Task::none();
+                }
 
+                // Only modifiers were pressed, so they make up the binding on their own.
+                let physical = self.bind_physical_keys.load(Ordering::Relaxed);
+                let pending = std::mem::take(&mut self.pending_modifier_keys);
+                let outputs: Option<Vec<MouseButtonOrKey>> =
+                    pending.iter().map(|k| k.to_output(physical)).collect();
+                match outputs.and_then(MouseButtonOrKey::from_outputs) {
+                    Some(output) => self.bind_captured_output(output),
+                    None => self.show_key_press_error(&captured),
+                }
+            }
+            Message::SetBindPhysicalKeys(bind_physical_keys) => {
+                self.bind_physical_keys
+                    .store(bind_physical_keys, Ordering::Relaxed);
+                Task::none()
+            }
+            Message::MouseButtonSet(slot, mb) => {
+                self.edit_gamepad_config(self.selected_gamepad_config(), |gc| {
+                    gc.insert_mouse_button(slot, mb)
+                });
+                Task::none()
+            }
+            Message::UnsetButton(slot) => {
+                self.edit_gamepad_config(self.selected_gamepad_config(), |gc| gc.remove_key(slot));
+                Task::none()
+            }
+            Message::SetTypedText(slot, typed) => {
+                self.edit_gamepad_config(self.selected_gamepad_config(), |gc| {
+                    gc.insert_output(slot, MouseButtonOrKey::Text(typed))
+                });
+                Task::none()
+            }
+            Message::SetLaunchCommand(slot, command) => {
+                self.edit_gamepad_config(self.selected_gamepad_config(), |gc| {
+                    gc.insert_output(slot, MouseButtonOrKey::Command(Box::new(command)))
+                });
                 Task::none()
             }
-            Message::UnsetButton(btn) => {
-                let mut profile_config = self.profile_config.lock().unwrap();
-                profile_config.unset_key_to_all(btn);
+            Message::SetButtonBinding(btn, binding) => {
+                self.edit_gamepad_config(self.selected_gamepad_config(), |gc| {
+                    gc.set_button_binding(btn, binding)
+                });
+                Task::none()
+            }
+            Message::AddAxisBinding(ab) => {
+                self.edit_gamepad_config(self.selected_gamepad_config(), |gc| {
+                    gc.add_axis_binding(ab.axis, ab.half)
+                });
+                Task::none()
+            }
+            Message::SetAxisBinding(ab) => {
+                self.edit_gamepad_config(self.selected_gamepad_config(), |gc| {
+                    gc.set_axis_binding(ab)
+                });
+                Task::none()
+            }
+            Message::RemoveAxisBinding(axis, half) => {
+                self.edit_gamepad_config(self.selected_gamepad_config(), |gc| {
+                    gc.remove_axis_binding(axis, half)
+                });
+                Task::none()
+            }
+            Message::OpenMacroEditorWindow(slot) => {
+                self.macro_window_target = Some((self.selected_gamepad_config(), slot));
+                self.open_window_near_last(
+                    Settings {
+                        size: Size::new(800_f32, 500_f32),
+                        ..Settings::default()
+                    },
+                    WindowType::MacroEditor,
+                )
+            }
+            Message::SetMacro(index, slot, m) => {
+                self.edit_gamepad_config(index, |gc| {
+                    gc.insert_output(slot, MouseButtonOrKey::Macro(m))
+                });
+                Task::none()
+            }
+            Message::OpenMacroStepKeySetWindow(index, slot, step) => {
+                self.open_key_set_window(KeyBindTarget::MacroStep(index, slot, step))
+            }
+            Message::OpenMacroRecorderWindow(slot) => {
+                self.macro_window_target = Some((self.selected_gamepad_config(), slot));
+                self.macro_recorder.lock().unwrap().clear();
+                self.open_window_near_last(
+                    Settings {
+                        size: Size::new(600_f32, 500_f32),
+                        ..Settings::default()
+                    },
+                    WindowType::MacroRecorder,
+                )
+            }
+            Message::StartMacroRecording => {
+                self.macro_recorder.lock().unwrap().start();
+                Task::none()
+            }
+            Message::StopMacroRecording => {
+                self.macro_recorder.lock().unwrap().stop();
+                Task::none()
+            }
+            Message::RecordEvent(event, at) => {
+                self.macro_recorder.lock().unwrap().record(
+                    &event,
+                    at,
+                    self.bind_physical_keys.load(Ordering::Relaxed),
+                );
+                Task::none()
+            }
+            Message::SetRecordedDelays(delays) => {
+                self.macro_recorder.lock().unwrap().delays = delays;
+                Task::none()
+            }
+            Message::SaveRecordedMacro(index, slot) => {
+                let m = self.macro_recorder.lock().unwrap().to_macro();
+                self.edit_gamepad_config(index, |gc| {
+                    gc.insert_output(slot, MouseButtonOrKey::Macro(m))
+                });
+                let recorder_window = self
+                    .windows
+                    .iter()
+                    .find(|(_, window)| window.window_type() == WindowType::MacroRecorder);
+                if let Some((id, _)) = recorder_window {
+                    return window::close(*id);
+                }
                 Task::none()
             }
             Message::SaveProfile => {
@@ -185,8 +617,9 @@ impl Application {
             Message::LoadProfile => {
                 let loaded_profile_config_opt = ProfileConfig::load().unwrap();
                 if let Some(loaded_profile_config) = loaded_profile_config_opt {
-                    let mut current_profile_config = self.profile_config.lock().unwrap();
-                    *current_profile_config = loaded_profile_config;
+                    *self.profile_config.lock().unwrap() = loaded_profile_config;
+                    self.selected_gamepad_config.store(0, Ordering::Relaxed);
+                    self.push_profile_to_handler();
                 }
                 Task::none()
             }
@@ -194,15 +627,59 @@ impl Application {
     }
 
     pub fn subscription(&self) -> Subscription<Message> {
-        let mut subs = vec![window::close_events().map(Message::WindowClosed)];
+        let mut subs = vec![
+            window::close_events().map(Message::WindowClosed),
+            Subscription::run(handler_status_worker),
+        ];
 
         if self.is_key_press_window_open() {
-            subs.push(iced::event::listen().map(|event| {
-                if let Event::Keyboard(keyboard::Event::KeyPressed { key, .. }) = event {
-                    Message::KeyPressed(key)
-                } else {
+            subs.push(iced::event::listen_with(|event, _, _| match event {
+                Event::Keyboard(keyboard::Event::KeyPressed {
+                    key,
+                    location,
+                    physical_key,
+                    modifiers,
+                    ..
+                }) => Some(Message::KeyPressed(CapturedKey {
+                    key,
+                    location,
+                    physical_key,
+                    modifiers,
+                })),
+                Event::Keyboard(keyboard::Event::KeyReleased {
+                    key,
+                    location,
+                    physical_key,
+                    modifiers,
+                    ..
+                }) => Some(Message::KeyReleased(CapturedKey {
+                    key,
+                    location,
+                    physical_key,
+                    modifiers,
+                })),
+                // Ignore other events
+                _ => None,
+            }))
+        }
+
+        if self.macro_recorder.lock().unwrap().is_recording() {
+            subs.push(iced::event::listen_with(|event, status, _| {
+                // Events used by a widget, like clicking the stop button, aren't part of the macro.
+                if status == event::Status::Captured {
+                    return None;
+                }
+                match event {
+                    Event::Keyboard(
+                        keyboard::Event::KeyPressed { .. } | keyboard::Event::KeyReleased { .. },
+                    )
+                    | Event::Mouse(
+                        mouse::Event::ButtonPressed(_)
+                        | mouse::Event::ButtonReleased(_)
+                        | mouse::Event::WheelScrolled { .. },
+                    ) => Some(Message::RecordEvent(event, Instant::now())),
                     // Ignore other events
-                    Message::KeyPressed(keyboard::Key::Unidentified)
+                    _ => None,
                 }
             }))
         }
@@ -217,3 +694,18 @@ impl Application {
         text("Error: window_id Not Found, could not load view!").into()
     }
 }
+
+// Relays the status of every handler thread to the application. The sending half of the channel is
+// handed over first, so that it can be given to the handler threads once they are spawned.
+fn handler_status_worker() -> impl Stream<Item = Message> {
+    iced::stream::channel(100, async |mut output| {
+        let (sender, mut receiver) = unbounded();
+        let _ = output
+            .send(Message::HandlerStatusChannelReady(sender))
+            .await;
+
+        while let Some(status) = receiver.next().await {
+            let _ = output.send(Message::HandlerStatusChanged(status)).await;
+        }
+    })
+}
@@ -0,0 +1,19 @@
+use crate::backend::controller_handler::HandlerStatus;
+use gilrs::Button;
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub enum HandlerActivity {
+    #[default]
+    Inactive,
+    Starting,
+    Active,
+    Stopping,
+}
+
+#[derive(Default)]
+pub struct HandlerState {
+    pub activity: HandlerActivity,
+    pub last_status: Option<HandlerStatus>,
+    // Toggle buttons the handler is currently holding, by gamepad config index.
+    pub latched_buttons: Vec<(usize, Button)>,
+}
@@ -1,2 +1,3 @@
 pub mod application;
+mod handler_state;
 mod window;
@@ -5,6 +5,8 @@ use iced::Element;
 pub enum WindowType {
     Main,
     KeyPress,
+    MacroEditor,
+    MacroRecorder,
     Error,
 }
 
@@ -1,72 +1,759 @@
-use crate::backend::config_manager::GamepadConfig;
-use crate::backend::joysticks::Joystick;
+use crate::backend::axis_binding::AxisBinding;
+use crate::backend::button_binding::{ButtonBinding, ExtraOutput, HoldAction, MultiTap, Turbo};
+use crate::backend::config_manager::{GamepadConfig, ProfileConfig};
+use crate::backend::display_bounds::EdgeBehavior;
+use crate::backend::gamepad_matcher::{ConnectedGamepad, GamepadMatcher};
+use crate::backend::input_source::{InputSource, OutputSlot};
+use crate::backend::joysticks::{Joystick, StickDirection};
 use crate::backend::key_utils::MouseButtonOrKey;
+use crate::backend::launch_command::LaunchCommand;
+use crate::backend::stick_config::{
+    DeadzoneShape, DirectionKeys, KeyDirections, ResponseCurve, ScrollAxes, ScrollStyle,
+    StickConfig, StickMode,
+};
 use crate::ui::application::Message;
+use crate::ui::window::gamepad_config_slot::GamepadConfigSlot;
 use crate::ui::window::mouse_button_wrapper::MouseButtonWrapper;
-use enigo::Button as MouseButton;
 use gilrs::Button;
-use iced::widget::{Row, Text, button, container, pick_list, row, text};
-use iced::{Color, Length};
+use iced::widget::{
+    Column, Row, Text, button, checkbox, column, container, pick_list, row, slider, space, text,
+    text_input, vertical_slider,
+};
+use iced::{Alignment, Color, Element, Length};
 
 pub fn header<'a>(content: &'a str) -> Text<'a> {
     text(content).size(20)
 }
 
-pub fn joystick_row<'c>(label: &'c str, joystick: Joystick, is_in_use: bool) -> Row<'c, Message> {
-    let msg = if is_in_use {
-        "controlling the mouse. ✔️"
+pub fn gamepad_config_selector<'s>(
+    profile_config: &ProfileConfig,
+    selected: usize,
+    connected_gamepads: &[ConnectedGamepad],
+) -> Column<'s, Message> {
+    let slots: Vec<GamepadConfigSlot> = (0..profile_config.gamepad_config_count())
+        .filter_map(|index| {
+            Some(GamepadConfigSlot {
+                index,
+                matcher: profile_config.get_gamepad_config(index)?.matcher.clone(),
+            })
+        })
+        .collect();
+    let selected_slot = slots.get(selected).cloned();
+    let selected_matcher = selected_slot.as_ref().map(|slot| slot.matcher.clone());
+
+    let mut matchers = vec![GamepadMatcher::Any];
+    for gamepad in connected_gamepads {
+        for matcher in [
+            GamepadMatcher::Uuid(gamepad.uuid.clone()),
+            GamepadMatcher::Name(gamepad.name.clone()),
+        ] {
+            if !matchers.contains(&matcher) {
+                matchers.push(matcher);
+            }
+        }
+    }
+    // Keep a matcher loaded from a profile selectable even if its controller isn't plugged in.
+    if let Some(matcher) = &selected_matcher
+        && !matchers.contains(matcher)
+    {
+        matchers.push(matcher.clone());
+    }
+
+    let connected_column = if connected_gamepads.is_empty() {
+        column![text("No controllers connected.")]
     } else {
-        "NOT controlling the mouse. ❌️"
+        Column::with_children(connected_gamepads.iter().map(|gamepad| {
+            row![
+                text(gamepad.name.clone()).color(Color::from_rgb8(255, 0, 0)),
+                text(format!(" (UUID {})", gamepad.uuid)),
+            ]
+            .into()
+        }))
     };
 
-    row![
-        text(label).color(Color::from_rgb8(255, 0, 0)),
-        text(" is currently "),
-        text(msg).color(Color::from_rgb8(0, 0, 255)),
-        container(button("Toggle").on_press(Message::ToggleAxisSelection(joystick)))
+    column![
+        row![
+            pick_list(slots, selected_slot, |slot| {
+                Message::SelectGamepadConfig(slot.index)
+            }),
+            button("Add").on_press(Message::AddGamepadConfig),
+            button("Duplicate").on_press(Message::DuplicateGamepadConfig),
+            button("Remove").on_press(Message::RemoveGamepadConfig),
+        ]
+        .spacing(10),
+        row![
+            text("Applies to: "),
+            pick_list(matchers, selected_matcher, Message::SetGamepadMatcher),
+            container(
+                checkbox(profile_config.is_fallback_gamepad_config(selected))
+                    .label("Use for unmatched controllers")
+                    .on_toggle(Message::SetFallbackGamepadConfig)
+            )
             .padding([0, 10]),
+        ],
+        row![
+            text("Connected controllers:"),
+            container(button("Refresh").on_press(Message::RefreshConnectedGamepads))
+                .padding([0, 10]),
+        ],
+        connected_column,
+    ]
+    .spacing(5)
+}
+
+pub fn joystick_row<'c>(label: &'c str, joystick: Joystick, sc: &StickConfig) -> Row<'c, Message> {
+    row![
+        text(label)
+            .color(Color::from_rgb8(255, 0, 0))
+            .width(Length::Fixed(150_f32)),
+        pick_list(
+            [
+                StickMode::Off,
+                StickMode::Cursor,
+                StickMode::Scroll,
+                StickMode::Keys,
+            ],
+            Some(sc.mode),
+            edit_stick_config(joystick, sc, |sc, v| sc.mode = v),
+        ),
+    ]
+    .spacing(10)
+}
+
+fn stick_slider_row<'s>(
+    label: &'s str,
+    range: std::ops::RangeInclusive<f32>,
+    value: f32,
+    on_change: impl Fn(f32) -> Message + 's,
+) -> Row<'s, Message> {
+    row![
+        text(label).width(Length::Fixed(150_f32)),
+        slider(range, value, on_change)
+            .step(0.01)
+            .width(Length::Fixed(200_f32)),
+        text(format!("{:.2}", value)),
+    ]
+    .spacing(10)
+}
+
+pub fn edit_copy<'s, V: Clone + 's, T>(
+    value: &V,
+    edit: impl Fn(&mut V, T) + 's,
+    to_message: impl Fn(V) -> Message + 's,
+) -> impl Fn(T) -> Message + 's {
+    let value = value.clone();
+    move |input| {
+        let mut value = value.clone();
+        edit(&mut value, input);
+        to_message(value)
+    }
+}
+
+// Re-selecting the kind of value that's already selected keeps its settings.
+pub fn reselect<T: Clone>(current: &T, selected: T) -> T {
+    if std::mem::discriminant(current) == std::mem::discriminant(&selected) {
+        current.clone()
+    } else {
+        selected
+    }
+}
+
+// Anything that isn't a number is ignored so a typo doesn't wipe the value.
+pub fn parse_ms(input: &str, current: u64) -> u64 {
+    if input.is_empty() {
+        0
+    } else {
+        input.parse().unwrap_or(current)
+    }
+}
+
+fn edit_stick_config<'s, T>(
+    joystick: Joystick,
+    sc: &StickConfig,
+    edit: impl Fn(&mut StickConfig, T) + 's,
+) -> impl Fn(T) -> Message + 's {
+    edit_copy(sc, edit, move |sc| Message::SetStickConfig(joystick, sc))
+}
+
+fn curve_preview<'s>(sc: &StickConfig) -> Row<'s, Message> {
+    const BAR_COUNT: usize = 32;
+    const PREVIEW_HEIGHT: f32 = 60.0;
+
+    Row::with_children((1..=BAR_COUNT).map(|i| {
+        let (amt, _) = sc.apply(i as f32 / BAR_COUNT as f32, 0.0);
+        let (amt, _) = sc.apply_response_curve(amt, 0.0);
+        container(space())
+            .width(Length::Fixed(5_f32))
+            .height(Length::Fixed(PREVIEW_HEIGHT * amt))
+            .style(|_| container::background(Color::from_rgb8(0, 0, 255)))
+            .into()
+    }))
+    .spacing(1)
+    .height(Length::Fixed(PREVIEW_HEIGHT))
+    .align_y(Alignment::End)
+}
+
+fn custom_curve_editor<'s>(joystick: Joystick, sc: &StickConfig) -> Row<'s, Message> {
+    let ResponseCurve::Custom(points) = &sc.response_curve else {
+        return row![];
+    };
+
+    Row::with_children(points.iter().enumerate().map(|(i, &point)| {
+        vertical_slider(
+            0.0..=1.0,
+            point,
+            edit_stick_config(joystick, sc, move |sc, v| {
+                if let ResponseCurve::Custom(points) = &mut sc.response_curve {
+                    points[i] = v;
+                }
+            }),
+        )
+        .step(0.01)
+        .height(Length::Fixed(80_f32))
+        .into()
+    }))
+    .spacing(10)
+}
+
+pub fn cursor_settings<'s>(profile_config: &ProfileConfig) -> Row<'s, Message> {
+    row![
+        text("At screen edges").width(Length::Fixed(150_f32)),
+        pick_list(
+            [EdgeBehavior::Stop, EdgeBehavior::Wrap],
+            Some(profile_config.edge_behavior),
+            Message::SetEdgeBehavior,
+        ),
+    ]
+    .spacing(10)
+}
+
+pub fn stick_settings<'s>(
+    label: &'s str,
+    joystick: Joystick,
+    gc: &GamepadConfig,
+) -> Column<'s, Message> {
+    let sc = gc.get_stick_config(&joystick);
+
+    let mut settings = column![joystick_row(label, joystick, sc)].spacing(5);
+    if sc.mode == StickMode::Off {
+        return settings;
+    }
+
+    settings = settings.push(
+        column![
+            stick_slider_row(
+                "Inner deadzone",
+                0.0..=StickConfig::MAX_INNER_DEADZONE,
+                sc.inner_deadzone,
+                edit_stick_config(joystick, sc, |sc, v| sc.inner_deadzone = v),
+            ),
+            stick_slider_row(
+                "Outer deadzone",
+                0.0..=StickConfig::MAX_OUTER_DEADZONE,
+                sc.outer_deadzone,
+                edit_stick_config(joystick, sc, |sc, v| sc.outer_deadzone = v),
+            ),
+            row![
+                text("Deadzone shape").width(Length::Fixed(150_f32)),
+                pick_list(
+                    [DeadzoneShape::Axial, DeadzoneShape::Radial],
+                    Some(sc.deadzone_shape),
+                    edit_stick_config(joystick, sc, |sc, v| sc.deadzone_shape = v),
+                ),
+            ]
+            .spacing(10),
+            row![
+                text("Response curve").width(Length::Fixed(150_f32)),
+                pick_list(
+                    ResponseCurve::options(),
+                    Some(sc.response_curve.clone()),
+                    edit_stick_config(joystick, sc, |sc, curve: ResponseCurve| {
+                        sc.response_curve = reselect(&sc.response_curve, curve)
+                    }),
+                ),
+            ]
+            .spacing(10),
+        ]
+        .spacing(5),
+    );
+
+    if let ResponseCurve::Exponential(steepness) = sc.response_curve {
+        settings = settings.push(stick_slider_row(
+            "Curve steepness",
+            0.5..=10.0,
+            steepness,
+            edit_stick_config(joystick, sc, |sc, v| {
+                sc.response_curve = ResponseCurve::Exponential(v)
+            }),
+        ));
+    }
+
+    settings = settings
+        .push(custom_curve_editor(joystick, sc))
+        .push(curve_preview(sc));
+
+    match sc.mode {
+        StickMode::Off => settings,
+        StickMode::Cursor => settings.push(cursor_stick_settings(joystick, sc)),
+        StickMode::Scroll => settings.push(scroll_stick_settings(joystick, sc)),
+        StickMode::Keys => settings.push(key_stick_settings(gc, joystick, sc)),
+    }
+}
+
+fn cursor_stick_settings<'s>(joystick: Joystick, sc: &StickConfig) -> Column<'s, Message> {
+    column![
+        checkbox(sc.relative_mouse)
+            .label("Relative movement (for games that lock the pointer)")
+            .on_toggle(edit_stick_config(joystick, sc, |sc, v| sc.relative_mouse = v)),
+        stick_slider_row(
+            "Sensitivity X",
+            0.1..=StickConfig::MAX_SENSITIVITY,
+            sc.sensitivity_x,
+            edit_stick_config(joystick, sc, |sc, v| sc.sensitivity_x = v),
+        ),
+        stick_slider_row(
+            "Sensitivity Y",
+            0.1..=StickConfig::MAX_SENSITIVITY,
+            sc.sensitivity_y,
+            edit_stick_config(joystick, sc, |sc, v| sc.sensitivity_y = v),
+        ),
+        stick_slider_row(
+            "Acceleration",
+            0.0..=StickConfig::MAX_ACCELERATION,
+            sc.acceleration,
+            edit_stick_config(joystick, sc, |sc, v| sc.acceleration = v),
+        ),
+        stick_slider_row(
+            "Max acceleration",
+            1.0..=StickConfig::MAX_ACCELERATION,
+            sc.max_acceleration,
+            edit_stick_config(joystick, sc, |sc, v| sc.max_acceleration = v),
+        ),
+    ]
+    .spacing(5)
+}
+
+fn scroll_stick_settings<'s>(joystick: Joystick, sc: &StickConfig) -> Column<'s, Message> {
+    column![
+        row![
+            text("Scroll direction").width(Length::Fixed(150_f32)),
+            pick_list(
+                [
+                    ScrollAxes::Both,
+                    ScrollAxes::Vertical,
+                    ScrollAxes::Horizontal
+                ],
+                Some(sc.scroll_axes),
+                edit_stick_config(joystick, sc, |sc, v| sc.scroll_axes = v),
+            ),
+        ]
+        .spacing(10),
+        row![
+            text("Scroll style").width(Length::Fixed(150_f32)),
+            pick_list(
+                [ScrollStyle::Smooth, ScrollStyle::Notches],
+                Some(sc.scroll_style),
+                edit_stick_config(joystick, sc, |sc, v| sc.scroll_style = v),
+            ),
+        ]
+        .spacing(10),
+        stick_slider_row(
+            "Notches per second",
+            1.0..=StickConfig::MAX_SCROLL_SPEED,
+            sc.scroll_speed,
+            edit_stick_config(joystick, sc, |sc, v| sc.scroll_speed = v),
+        ),
     ]
-    .width(Length::Fill)
+    .spacing(5)
+}
+
+fn key_stick_settings<'s>(
+    gc: &GamepadConfig,
+    joystick: Joystick,
+    sc: &StickConfig,
+) -> Column<'s, Message> {
+    let mut wasd = sc.clone();
+    wasd.direction_keys = DirectionKeys::wasd();
+    let mut arrows = sc.clone();
+    arrows.direction_keys = DirectionKeys::arrows();
+
+    let mut settings = column![
+        row![
+            text("Directions").width(Length::Fixed(150_f32)),
+            pick_list(
+                [KeyDirections::FourWay, KeyDirections::EightWay],
+                Some(sc.key_directions),
+                edit_stick_config(joystick, sc, |sc, v| sc.key_directions = v),
+            ),
+        ]
+        .spacing(10),
+        stick_slider_row(
+            "Press threshold",
+            0.1..=1.0,
+            sc.key_press_threshold,
+            edit_stick_config(joystick, sc, |sc, v| {
+                sc.key_press_threshold = v;
+                sc.key_release_threshold = sc.key_release_threshold.min(v);
+            }),
+        ),
+        stick_slider_row(
+            "Release threshold",
+            0.0..=1.0,
+            sc.key_release_threshold,
+            edit_stick_config(joystick, sc, |sc, v: f32| {
+                sc.key_release_threshold = v.min(sc.key_press_threshold)
+            }),
+        ),
+        row![
+            text("Presets").width(Length::Fixed(150_f32)),
+            button("WASD").on_press(Message::SetStickConfig(joystick, wasd)),
+            button("Arrows").on_press(Message::SetStickConfig(joystick, arrows)),
+        ]
+        .spacing(10),
+    ]
+    .spacing(5);
+
+    for (label, direction) in [
+        ("Up", StickDirection::Up),
+        ("Down", StickDirection::Down),
+        ("Left", StickDirection::Left),
+        ("Right", StickDirection::Right),
+    ] {
+        settings = settings.push(input_mapper_row(
+            label,
+            InputSource::StickDirection(joystick, direction),
+            gc,
+        ));
+    }
+    settings
 }
 
-fn get_str_from_config(gc: &GamepadConfig, gilrs_btn: &Button) -> String {
+fn get_str_from_config(gc: &GamepadConfig, slot: &OutputSlot) -> String {
     // TODO: Move gc.get_key(...) out of this function!
-    if let Some(key) = gc.get_key(gilrs_btn) {
+    if let Some(key) = gc.get_key(slot) {
         return key.to_string();
     }
     "None".to_string()
 }
 
-pub fn button_mapper_row<'b>(label: &'b str, btn: Button, gc: &GamepadConfig) -> Row<'b, Message> {
-    let mouse_buttons = [
-        MouseButtonWrapper(MouseButton::Left),
-        MouseButtonWrapper(MouseButton::Middle),
-        MouseButtonWrapper(MouseButton::Right),
-        MouseButtonWrapper(MouseButton::Back),
-        MouseButtonWrapper(MouseButton::Forward),
-        MouseButtonWrapper(MouseButton::ScrollUp),
-        MouseButtonWrapper(MouseButton::ScrollDown),
-        MouseButtonWrapper(MouseButton::ScrollLeft),
-        MouseButtonWrapper(MouseButton::ScrollRight),
+pub fn button_mapper_row<'b>(
+    label: &'b str,
+    btn: Button,
+    gc: &GamepadConfig,
+    is_latched: bool,
+) -> Column<'b, Message> {
+    let mapper = input_mapper_row(label, InputSource::Button(btn), gc);
+    match gc.get_button_binding(&btn) {
+        Some(binding) => mapper.push(button_binding_settings(btn, binding, gc, is_latched)),
+        None => mapper,
+    }
+}
+
+fn edit_button_binding<'s, T>(
+    btn: Button,
+    binding: &ButtonBinding,
+    edit: impl Fn(&mut ButtonBinding, T) + 's,
+) -> impl Fn(T) -> Message + 's {
+    edit_copy(binding, edit, move |binding| {
+        Message::SetButtonBinding(btn, binding)
+    })
+}
+
+fn button_binding_settings<'s>(
+    btn: Button,
+    binding: &ButtonBinding,
+    gc: &GamepadConfig,
+    is_latched: bool,
+) -> Column<'s, Message> {
+    let mut settings = column![
+        checkbox(binding.hold.is_some())
+            .label("Different action when held")
+            .on_toggle(edit_button_binding(btn, binding, |binding, is_on: bool| {
+                binding.hold = is_on.then(HoldAction::default)
+            })),
+    ]
+    .spacing(5);
+    if let Some(hold) = &binding.hold {
+        settings = settings.push(extra_output_row(
+            "When held, press: ",
+            btn,
+            ExtraOutput::Hold,
+            gc,
+        ));
+        settings = settings.push(
+            row![
+                text("Hold threshold").width(Length::Fixed(150_f32)),
+                slider(
+                    HoldAction::MIN_THRESHOLD_MS..=HoldAction::MAX_THRESHOLD_MS,
+                    hold.threshold_ms,
+                    edit_button_binding(btn, binding, |binding, v| {
+                        if let Some(hold) = &mut binding.hold {
+                            hold.threshold_ms = v;
+                        }
+                    }),
+                )
+                .step(10_u32)
+                .width(Length::Fixed(200_f32)),
+                text(format!("{} ms", hold.threshold_ms)),
+            ]
+            .spacing(10),
+        );
+    }
+
+    settings = settings.push(
+        checkbox(binding.multi_tap.is_some())
+            .label("Different actions for double and triple taps")
+            .on_toggle(edit_button_binding(btn, binding, |binding, is_on: bool| {
+                binding.multi_tap = is_on.then(MultiTap::default)
+            })),
+    );
+    if let Some(multi_tap) = &binding.multi_tap {
+        settings = settings.push(extra_output_row(
+            "When double tapped, press: ",
+            btn,
+            ExtraOutput::DoubleTap,
+            gc,
+        ));
+        settings = settings.push(extra_output_row(
+            "When triple tapped, press: ",
+            btn,
+            ExtraOutput::TripleTap,
+            gc,
+        ));
+        settings = settings.push(
+            row![
+                text("Tap window").width(Length::Fixed(150_f32)),
+                slider(
+                    MultiTap::MIN_WINDOW_MS..=MultiTap::MAX_WINDOW_MS,
+                    multi_tap.window_ms,
+                    edit_button_binding(btn, binding, |binding, v| {
+                        if let Some(multi_tap) = &mut binding.multi_tap {
+                            multi_tap.window_ms = v;
+                        }
+                    }),
+                )
+                .step(10_u32)
+                .width(Length::Fixed(200_f32)),
+                text(format!("{} ms", multi_tap.window_ms)),
+            ]
+            .spacing(10),
+        );
+    }
+    // Toggle and turbo need the output pressed the moment the button goes down.
+    if binding.is_delayed() {
+        return settings;
+    }
+
+    if !binding.can_toggle() {
+        return settings;
+    }
+
+    let latched_text = if is_latched {
+        text("Held").color(Color::from_rgb8(0, 150, 0))
+    } else {
+        text("")
+    };
+    settings = settings.push(
+        row![
+            checkbox(binding.toggle)
+                .label("Toggle (press once to hold, again to release)")
+                .on_toggle(edit_button_binding(btn, binding, |binding, is_on| {
+                    binding.toggle = is_on
+                })),
+            latched_text,
+        ]
+        .spacing(10),
+    );
+    if !binding.can_turbo() {
+        return settings;
+    }
+
+    settings = settings.push(
+        checkbox(binding.turbo.is_some())
+            .label("Turbo (repeat while held)")
+            .on_toggle(edit_button_binding(btn, binding, |binding, is_on: bool| {
+                binding.turbo = is_on.then(Turbo::default)
+            })),
+    );
+    if let Some(turbo) = binding.turbo {
+        settings = settings.push(stick_slider_row(
+            "Presses per second",
+            1.0..=Turbo::MAX_RATE,
+            turbo.rate,
+            edit_button_binding(btn, binding, move |binding, v| {
+                binding.turbo = Some(Turbo { rate: v, ..turbo })
+            }),
+        ));
+        settings = settings.push(stick_slider_row(
+            "Held for",
+            0.1..=0.9,
+            turbo.duty_cycle,
+            edit_button_binding(btn, binding, move |binding, v| {
+                binding.turbo = Some(Turbo {
+                    duty_cycle: v,
+                    ..turbo
+                })
+            }),
+        ));
+    }
+    settings
+}
+
+fn extra_output_row<'b>(
+    label: &'b str,
+    btn: Button,
+    extra: ExtraOutput,
+    gc: &GamepadConfig,
+) -> Column<'b, Message> {
+    output_editor(row![text(label)], OutputSlot::Extra(btn, extra), gc)
+}
+
+fn input_mapper_row<'b>(
+    label: impl text::IntoFragment<'b>,
+    source: InputSource,
+    gc: &GamepadConfig,
+) -> Column<'b, Message> {
+    let label_row = row![
+        text(label).color(Color::from_rgb8(255, 0, 0)),
+        text(" is currently assigned to: ".to_string()),
     ];
+    output_editor(label_row, OutputSlot::Input(source), gc)
+}
 
+fn output_editor<'b>(
+    label_row: Row<'b, Message>,
+    slot: OutputSlot,
+    gc: &GamepadConfig,
+) -> Column<'b, Message> {
     let mut selected_mouse_button: Option<MouseButtonWrapper> = None;
-    if let Some(mb_key) = gc.get_key(&btn) {
-        if let MouseButtonOrKey::MouseButton(mb) = mb_key {
-            selected_mouse_button = Some(MouseButtonWrapper(*mb));
-        }
+    if let Some(MouseButtonOrKey::MouseButton(mb)) = gc.get_key(&slot) {
+        selected_mouse_button = Some(MouseButtonWrapper(*mb));
     }
 
+    // Text bindings are edited right here, anything else can be switched over to one.
+    let typed_text: Element<'b, Message> = match gc.get_key(&slot) {
+        Some(MouseButtonOrKey::Text(typed)) => text_input("Text to type...", typed)
+            .on_input(move |typed| Message::SetTypedText(slot, typed))
+            .width(Length::Fixed(200_f32))
+            .into(),
+        _ => button("Type Text")
+            .on_press(Message::SetTypedText(slot, String::new()))
+            .into(),
+    };
+
+    let command = match gc.get_key(&slot) {
+        Some(MouseButtonOrKey::Command(command)) => Some(command),
+        _ => None,
+    };
+    let run_command = button("Run Command").on_press_maybe(
+        command
+            .is_none()
+            .then(|| Message::SetLaunchCommand(slot, LaunchCommand::default())),
+    );
+
+    let mapper_row = label_row
+        .push(text(get_str_from_config(gc, &slot)).color(Color::from_rgb8(0, 0, 255)))
+        .push(
+            container(button("Assign Key").on_press(Message::OpenKeySetWindow(slot)))
+                .padding([0, 10]),
+        )
+        .push(container(button("Unassign").on_press(Message::UnsetButton(slot))).padding([0, 10]))
+        .push(
+            container(button("Edit Macro").on_press(Message::OpenMacroEditorWindow(slot)))
+                .padding([0, 10]),
+        )
+        .push(
+            container(button("Record Macro").on_press(Message::OpenMacroRecorderWindow(slot)))
+                .padding([0, 10]),
+        )
+        .push(
+            pick_list(MouseButtonWrapper::ALL, selected_mouse_button, move |mbw| {
+                Message::MouseButtonSet(slot, mbw.0)
+            })
+            .placeholder("Select a mouse button..."),
+        )
+        .push(container(typed_text).padding([0, 10]))
+        .push(run_command)
+        .width(Length::Fill);
+
+    match command {
+        Some(command) => column![mapper_row, command_editor(slot, command)].spacing(5),
+        None => column![mapper_row].spacing(5),
+    }
+}
+
+fn command_editor<'b>(slot: OutputSlot, command: &LaunchCommand) -> Row<'b, Message> {
+    let edit_command = |edit: fn(&mut LaunchCommand, String)| {
+        edit_copy(command, edit, move |command| {
+            Message::SetLaunchCommand(slot, command)
+        })
+    };
+
     row![
-        text(label).color(Color::from_rgb8(255, 0, 0)),
-        text(" is currently assigned to: ".to_string()),
-        text(get_str_from_config(gc, &btn)).color(Color::from_rgb8(0, 0, 255)),
-        container(button("Assign Key").on_press(Message::OpenKeySetWindow(btn))).padding([0, 10]),
-        container(button("Unassign").on_press(Message::UnsetButton(btn))).padding([0, 10]),
-        pick_list(mouse_buttons, selected_mouse_button, move |mbw| {
-            Message::MouseButtonSet(btn, mbw.0)
+        text("Command").width(Length::Fixed(150_f32)),
+        text_input("Program, e.g. alacritty", &command.program)
+            .on_input(edit_command(|command, v| command.program = v))
+            .width(Length::Fixed(200_f32)),
+        text_input("Arguments, \"quote\" ones with spaces", &command.args)
+            .on_input(edit_command(|command, v| command.args = v))
+            .width(Length::Fixed(250_f32)),
+        text_input("Working directory (optional)", &command.working_dir)
+            .on_input(edit_command(|command, v| command.working_dir = v))
+            .width(Length::Fixed(200_f32)),
+    ]
+    .spacing(10)
+}
+
+pub fn axis_bindings<'a>(gc: &GamepadConfig) -> Column<'a, Message> {
+    let unbound: Vec<AxisBinding> = AxisBinding::options()
+        .into_iter()
+        .filter(|option| {
+            !gc.get_axis_bindings()
+                .iter()
+                .any(|ab| ab.axis == option.axis && ab.half == option.half)
         })
-        .placeholder("Select a mouse button..."),
+        .collect();
+
+    let mut bindings = column![
+        pick_list(unbound, None::<AxisBinding>, Message::AddAxisBinding)
+            .placeholder("Bind an axis...")
     ]
-    .width(Length::Fill)
+    .spacing(5);
+
+    for ab in gc.get_axis_bindings() {
+        let edit_axis_binding = |edit: fn(&mut AxisBinding, f32)| {
+            let ab = ab.clone();
+            move |value| {
+                let mut ab = ab.clone();
+                edit(&mut ab, value);
+                Message::SetAxisBinding(ab)
+            }
+        };
+
+        bindings = bindings.push(
+            column![
+                row![
+                    input_mapper_row(ab.to_string(), InputSource::Axis(ab.axis, ab.half), gc),
+                    button("Remove").on_press(Message::RemoveAxisBinding(ab.axis, ab.half)),
+                ],
+                stick_slider_row(
+                    "Press threshold",
+                    0.05..=1.0,
+                    ab.press_threshold,
+                    edit_axis_binding(|ab, v| {
+                        ab.press_threshold = v;
+                        ab.release_threshold = ab.release_threshold.min(v);
+                    }),
+                ),
+                stick_slider_row(
+                    "Release threshold",
+                    0.0..=1.0,
+                    ab.release_threshold,
+                    edit_axis_binding(|ab, v| ab.release_threshold = v.min(ab.press_threshold)),
+                ),
+            ]
+            .spacing(5),
+        );
+    }
+    bindings
 }
@@ -0,0 +1,14 @@
+use crate::backend::gamepad_matcher::GamepadMatcher;
+use std::fmt::{Display, Formatter};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GamepadConfigSlot {
+    pub index: usize,
+    pub matcher: GamepadMatcher,
+}
+
+impl Display for GamepadConfigSlot {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Controller {} ({})", self.index + 1, self.matcher)
+    }
+}
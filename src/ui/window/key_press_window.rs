@@ -1,9 +1,24 @@
 use crate::ui::application::Message;
 use crate::ui::window::base::{Window, WindowType};
-use iced::Element;
-use iced::widget::{row, text};
+use iced::widget::{checkbox, column, text};
+use iced::{Color, Element};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 
-pub struct KeyPressWindow;
+pub struct KeyPressWindow {
+    // Set when the last key that was pressed can't be assigned, so another one can be tried.
+    error: Arc<Mutex<Option<String>>>,
+    bind_physical_keys: Arc<AtomicBool>,
+}
+
+impl KeyPressWindow {
+    pub fn new(error: Arc<Mutex<Option<String>>>, bind_physical_keys: Arc<AtomicBool>) -> Self {
+        Self {
+            error,
+            bind_physical_keys,
+        }
+    }
+}
 
 impl Window for KeyPressWindow {
     fn window_type(&self) -> WindowType {
@@ -11,6 +26,19 @@ impl Window for KeyPressWindow {
     }
 
     fn view(&self) -> Element<'_, Message> {
-        row![text("Please press a key to assign it.")].into()
+        let error = self.error.lock().unwrap();
+        let error_text = match error.as_ref() {
+            Some(e) => text(e.clone()).color(Color::from_rgb8(200, 0, 0)),
+            None => text(""),
+        };
+        column![
+            text("Please press a key or key combination to assign it."),
+            checkbox(self.bind_physical_keys.load(Ordering::Relaxed))
+                .label("Bind the physical key (works the same on every keyboard layout)")
+                .on_toggle(Message::SetBindPhysicalKeys),
+            error_text
+        ]
+        .spacing(5)
+        .into()
     }
 }
@@ -0,0 +1,174 @@
+use crate::backend::config_manager::ProfileConfig;
+use crate::backend::input_source::OutputSlot;
+use crate::backend::key_utils::MouseButtonOrKey;
+use crate::backend::macros::{Macro, MacroStep};
+use crate::ui::application::Message;
+use crate::ui::window::base::{Window, WindowType};
+use crate::ui::window::components::{edit_copy, header, parse_ms};
+use crate::ui::window::mouse_button_wrapper::MouseButtonWrapper;
+use enigo::Button as MouseButton;
+use iced::widget::{
+    Column, Row, button, checkbox, column, pick_list, row, scrollable, text, text_input,
+};
+use iced::{Color, Element, Length};
+use std::sync::{Arc, Mutex};
+
+pub struct MacroEditorWindow {
+    profile_config: Arc<Mutex<ProfileConfig>>,
+    gamepad_config_index: usize,
+    slot: OutputSlot,
+}
+
+impl MacroEditorWindow {
+    pub fn new(
+        profile_config: Arc<Mutex<ProfileConfig>>,
+        gamepad_config_index: usize,
+        slot: OutputSlot,
+    ) -> Self {
+        Self {
+            profile_config,
+            gamepad_config_index,
+            slot,
+        }
+    }
+
+    fn edit_macro<'s, T>(
+        &self,
+        m: &Macro,
+        edit: impl Fn(&mut Macro, T) + 's,
+    ) -> impl Fn(T) -> Message + 's {
+        let (index, slot) = (self.gamepad_config_index, self.slot);
+        edit_copy(m, edit, move |m| Message::SetMacro(index, slot, m))
+    }
+
+    fn step_row<'s>(&self, m: &Macro, i: usize) -> Row<'s, Message> {
+        let step_editor: Element<'s, Message> = match &m.steps[i] {
+            MacroStep::KeyDown(output) | MacroStep::KeyUp(output) => row![
+                text(
+                    output
+                        .as_ref()
+                        .map_or("None".to_string(), |o| o.to_string())
+                )
+                .color(Color::from_rgb8(0, 0, 255)),
+                button("Assign Key").on_press(Message::OpenMacroStepKeySetWindow(
+                    self.gamepad_config_index,
+                    self.slot,
+                    i,
+                )),
+            ]
+            .spacing(10)
+            .into(),
+            MacroStep::Click(mb) => pick_list(
+                MouseButtonWrapper::ALL,
+                Some(MouseButtonWrapper(*mb)),
+                self.edit_macro(m, move |m, mbw: MouseButtonWrapper| {
+                    m.steps[i] = MacroStep::Click(mbw.0)
+                }),
+            )
+            .into(),
+            MacroStep::Text(typed) => text_input("Text to type", typed)
+                .on_input(self.edit_macro(m, move |m, typed| m.steps[i] = MacroStep::Text(typed)))
+                .width(Length::Fixed(250_f32))
+                .into(),
+            &MacroStep::Wait(ms) => row![
+                text_input("0", &ms.to_string())
+                    .on_input(self.edit_macro(m, move |m, input: String| {
+                        m.steps[i] = MacroStep::Wait(parse_ms(&input, ms))
+                    }))
+                    .width(Length::Fixed(100_f32)),
+                text("ms"),
+            ]
+            .spacing(10)
+            .into(),
+        };
+
+        let moved = |to: usize| {
+            let mut moved = m.clone();
+            moved.steps.swap(i, to);
+            Message::SetMacro(self.gamepad_config_index, self.slot, moved)
+        };
+        let mut removed = m.clone();
+        removed.steps.remove(i);
+
+        row![
+            text(format!("{}. {}", i + 1, m.steps[i])).width(Length::Fixed(120_f32)),
+            step_editor,
+            button("Up").on_press_maybe((i > 0).then(|| moved(i - 1))),
+            button("Down").on_press_maybe((i + 1 < m.steps.len()).then(|| moved(i + 1))),
+            button("Remove").on_press(Message::SetMacro(
+                self.gamepad_config_index,
+                self.slot,
+                removed,
+            )),
+        ]
+        .spacing(10)
+    }
+
+    fn add_step_row<'s>(&self, m: &Macro) -> Row<'s, Message> {
+        let added = |step: MacroStep| {
+            let mut added = m.clone();
+            added.steps.push(step);
+            Message::SetMacro(self.gamepad_config_index, self.slot, added)
+        };
+
+        row![
+            button("Add key down").on_press(added(MacroStep::KeyDown(None))),
+            button("Add key up").on_press(added(MacroStep::KeyUp(None))),
+            button("Add click").on_press(added(MacroStep::Click(MouseButton::Left))),
+            button("Add text").on_press(added(MacroStep::Text(String::new()))),
+            button("Add wait").on_press(added(MacroStep::Wait(100))),
+        ]
+        .spacing(10)
+    }
+}
+
+impl Window for MacroEditorWindow {
+    fn window_type(&self) -> WindowType {
+        WindowType::MacroEditor
+    }
+
+    fn view(&self) -> Element<'_, Message> {
+        let profile_config = self.profile_config.lock().unwrap();
+        let Some(gc) = profile_config.get_gamepad_config(self.gamepad_config_index) else {
+            return text("Error: controller config not found!").into();
+        };
+
+        // Until the first edit the input keeps whatever it was bound to before.
+        let (m, replace_warning) = match gc.get_key(&self.slot) {
+            Some(MouseButtonOrKey::Macro(m)) => (m.clone(), text("")),
+            Some(output) => (
+                Macro::default(),
+                text(format!(
+                    "Editing this macro will replace the current binding ({}).",
+                    output
+                ))
+                .color(Color::from_rgb8(200, 0, 0)),
+            ),
+            None => (Macro::default(), text("")),
+        };
+
+        let steps = Column::with_children((0..m.steps.len()).map(|i| self.step_row(&m, i).into()))
+            .spacing(5);
+
+        column![
+            header("Macro"),
+            row![
+                text(self.slot.to_string()).color(Color::from_rgb8(255, 0, 0)),
+                text(" plays this macro when pressed."),
+            ],
+            replace_warning,
+            checkbox(m.cancel_on_release)
+                .label("Stop when released")
+                .on_toggle(self.edit_macro(&m, |m, v| m.cancel_on_release = v)),
+            checkbox(m.repeat_while_held)
+                .label("Repeat while held")
+                .on_toggle(self.edit_macro(&m, |m, v| m.repeat_while_held = v)),
+            header("Steps"),
+            scrollable(steps).height(Length::Fill),
+            self.add_step_row(&m),
+        ]
+        .spacing(5)
+        .height(Length::Fill)
+        .into()
+    }
+}
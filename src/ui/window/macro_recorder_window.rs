@@ -0,0 +1,149 @@
+use crate::backend::input_source::OutputSlot;
+use crate::backend::macro_recorder::{MacroRecorder, RecordedDelays};
+use crate::backend::macros::MacroStep;
+use crate::ui::application::Message;
+use crate::ui::window::base::{Window, WindowType};
+use crate::ui::window::components::{header, parse_ms, reselect};
+use iced::widget::{
+    Column, Row, button, checkbox, column, pick_list, row, scrollable, slider, text, text_input,
+};
+use iced::{Color, Element, Length};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+pub struct MacroRecorderWindow {
+    recorder: Arc<Mutex<MacroRecorder>>,
+    bind_physical_keys: Arc<AtomicBool>,
+    gamepad_config_index: usize,
+    slot: OutputSlot,
+}
+
+impl MacroRecorderWindow {
+    pub fn new(
+        recorder: Arc<Mutex<MacroRecorder>>,
+        bind_physical_keys: Arc<AtomicBool>,
+        gamepad_config_index: usize,
+        slot: OutputSlot,
+    ) -> Self {
+        Self {
+            recorder,
+            bind_physical_keys,
+            gamepad_config_index,
+            slot,
+        }
+    }
+}
+
+fn describe_step(step: &MacroStep) -> String {
+    match step {
+        MacroStep::KeyDown(Some(output)) | MacroStep::KeyUp(Some(output)) => {
+            format!("{} {}", step, output)
+        }
+        MacroStep::Click(mb) => format!("{} {:?}", step, mb),
+        MacroStep::Wait(ms) => format!("{} {} ms", step, ms),
+        _ => step.to_string(),
+    }
+}
+
+fn delay_settings<'s>(delays: &RecordedDelays) -> Row<'s, Message> {
+    let current = delays.clone();
+    let mut settings = row![
+        text("Delays").width(Length::Fixed(150_f32)),
+        pick_list(
+            RecordedDelays::options(),
+            Some(delays.clone()),
+            move |selected| Message::SetRecordedDelays(reselect(&current, selected)),
+        ),
+    ]
+    .spacing(10);
+
+    settings = settings.push(match *delays {
+        RecordedDelays::AsRecorded => row![],
+        RecordedDelays::Scaled(scale) => row![
+            slider(0.1..=RecordedDelays::MAX_SCALE, scale, |scale| {
+                Message::SetRecordedDelays(RecordedDelays::Scaled(scale))
+            })
+            .step(0.05)
+            .width(Length::Fixed(200_f32)),
+            text(format!("x{:.2}", scale)),
+        ]
+        .spacing(10),
+        RecordedDelays::Fixed(ms) => row![
+            text_input("0", &ms.to_string())
+                .on_input(move |input| {
+                    Message::SetRecordedDelays(RecordedDelays::Fixed(parse_ms(&input, ms)))
+                })
+                .width(Length::Fixed(100_f32)),
+            text("ms"),
+        ]
+        .spacing(10),
+    });
+    settings
+}
+
+impl Window for MacroRecorderWindow {
+    fn window_type(&self) -> WindowType {
+        WindowType::MacroRecorder
+    }
+
+    fn view(&self) -> Element<'_, Message> {
+        let recorder = self.recorder.lock().unwrap();
+        let recorded = recorder.to_macro();
+
+        let status_text = if recorder.is_recording() {
+            text("Recording... Press keys, or click and scroll in this window.")
+                .color(Color::from_rgb8(200, 0, 0))
+        } else if recorder.is_empty() {
+            text("Nothing has been recorded yet.")
+        } else {
+            text(format!("{} steps recorded.", recorded.steps.len()))
+        };
+        let skipped_text = match recorder.skipped_keys() {
+            0 => text(""),
+            skipped => text(format!("{} key presses couldn't be recorded.", skipped))
+                .color(Color::from_rgb8(200, 0, 0)),
+        };
+
+        let steps = Column::with_children(
+            recorded
+                .steps
+                .iter()
+                .enumerate()
+                .map(|(i, step)| text(format!("{}. {}", i + 1, describe_step(step))).into()),
+        );
+
+        column![
+            header("Record Macro"),
+            row![
+                text(self.slot.to_string()).color(Color::from_rgb8(255, 0, 0)),
+                text(" will play the recorded macro when pressed."),
+            ],
+            row![
+                button("Start Recording").on_press_maybe(
+                    (!recorder.is_recording()).then_some(Message::StartMacroRecording)
+                ),
+                button("Stop Recording").on_press_maybe(
+                    recorder
+                        .is_recording()
+                        .then_some(Message::StopMacroRecording)
+                ),
+                status_text,
+            ]
+            .spacing(10),
+            checkbox(self.bind_physical_keys.load(Ordering::Relaxed))
+                .label("Record physical keys (works the same on every keyboard layout)")
+                .on_toggle(Message::SetBindPhysicalKeys),
+            skipped_text,
+            delay_settings(&recorder.delays),
+            scrollable(steps).height(Length::Fill),
+            button("Save").on_press_maybe(
+                (!recorder.is_recording() && !recorder.is_empty()).then_some(
+                    Message::SaveRecordedMacro(self.gamepad_config_index, self.slot)
+                ),
+            ),
+        ]
+        .spacing(5)
+        .height(Length::Fill)
+        .into()
+    }
+}
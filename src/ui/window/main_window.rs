@@ -1,27 +1,38 @@
 use crate::backend::config_manager::ProfileConfig;
+use crate::backend::gamepad_matcher::ConnectedGamepad;
 use crate::backend::joysticks::Joystick;
 use crate::ui::application::Message;
+use crate::ui::handler_state::{HandlerActivity, HandlerState};
 use crate::ui::window::base::{Window, WindowType};
-use crate::ui::window::components::{button_mapper_row, header, joystick_row};
+use crate::ui::window::components::{
+    axis_bindings, button_mapper_row, cursor_settings, gamepad_config_selector, header,
+    stick_settings,
+};
 use gilrs::Button;
 use iced::widget::{button, column, row, scrollable, text};
 use iced::{Color, Element, Length};
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 
 pub struct MainWindow {
     profile_config: Arc<Mutex<ProfileConfig>>,
-    is_handler_running: Arc<AtomicBool>,
+    handler_state: Arc<Mutex<HandlerState>>,
+    selected_gamepad_config: Arc<AtomicUsize>,
+    connected_gamepads: Arc<Mutex<Vec<ConnectedGamepad>>>,
 }
 
 impl MainWindow {
     pub fn new(
         profile_config: Arc<Mutex<ProfileConfig>>,
-        is_handler_running: Arc<AtomicBool>,
+        handler_state: Arc<Mutex<HandlerState>>,
+        selected_gamepad_config: Arc<AtomicUsize>,
+        connected_gamepads: Arc<Mutex<Vec<ConnectedGamepad>>>,
     ) -> Self {
         Self {
             profile_config,
-            is_handler_running,
+            handler_state,
+            selected_gamepad_config,
+            connected_gamepads,
         }
     }
 }
@@ -33,88 +44,83 @@ impl Window for MainWindow {
 
     fn view(&self) -> Element<'_, Message> {
         let profile_config = self.profile_config.lock().unwrap();
-        // TODO: Add a dropdown to support multiple gamepads!
-        let single_active_gamepad_config = profile_config.get_first_gamepad_config();
+        let connected_gamepads = self.connected_gamepads.lock().unwrap();
+        let selected = self.selected_gamepad_config.load(Ordering::Relaxed);
+        let Some(gamepad_config) = profile_config.get_gamepad_config(selected) else {
+            return text("Error: selected controller config not found!").into();
+        };
 
         let activate = button("Activate").on_press(Message::Activate);
         let deactivate = button("Deactivate").on_press(Message::Deactivate);
-        let handler_text = if self.is_handler_running.load(Ordering::Relaxed) {
-            text("Controller Active!").color(Color::from_rgb8(0, 150, 0))
-        } else {
-            text("Controller Inactive")
+        let handler_state = self.handler_state.lock().unwrap();
+        let handler_text = match handler_state.activity {
+            HandlerActivity::Active => {
+                text("Controller Active!").color(Color::from_rgb8(0, 150, 0))
+            }
+            HandlerActivity::Starting => text("Starting..."),
+            HandlerActivity::Stopping => text("Stopping..."),
+            HandlerActivity::Inactive => text("Controller Inactive"),
+        };
+        let handler_status_text = match &handler_state.last_status {
+            Some(status) => text(format!("({})", status)),
+            None => text(""),
         };
 
+        // Toggle buttons only show as held for the config that's being edited.
+        let latched: Vec<Button> = handler_state
+            .latched_buttons
+            .iter()
+            .filter(|(index, _)| *index == selected)
+            .map(|(_, btn)| *btn)
+            .collect();
+        let button_row =
+            |label, btn| button_mapper_row(label, btn, gamepad_config, latched.contains(&btn));
+
         let save_profile = button("Save Profile").on_press(Message::SaveProfile);
         let load_profile = button("Load Profile").on_press(Message::LoadProfile);
 
         column![
             scrollable(
                 column![
+                    header("Controllers"),
+                    gamepad_config_selector(&profile_config, selected, &connected_gamepads),
+                    header("Cursor"),
+                    cursor_settings(&profile_config),
                     header("Joystick Axes"),
-                    joystick_row(
-                        "Left Joystick",
-                        Joystick::Left,
-                        single_active_gamepad_config.use_left_stick_mouse
-                    ),
-                    joystick_row(
-                        "Right Joystick",
-                        Joystick::Right,
-                        single_active_gamepad_config.use_right_stick_mouse
-                    ),
+                    stick_settings("Left Joystick", Joystick::Left, gamepad_config),
+                    stick_settings("Right Joystick", Joystick::Right, gamepad_config),
                     header("Menu Pad"),
-                    button_mapper_row("Start", Button::Start, single_active_gamepad_config),
-                    button_mapper_row("Select", Button::Select, single_active_gamepad_config),
-                    button_mapper_row("Mode", Button::Mode, single_active_gamepad_config),
+                    button_row("Start", Button::Start),
+                    button_row("Select", Button::Select),
+                    button_row("Mode", Button::Mode),
                     header("Action Pad"),
-                    button_mapper_row("North", Button::North, single_active_gamepad_config),
-                    button_mapper_row("West", Button::West, single_active_gamepad_config),
-                    button_mapper_row("East", Button::East, single_active_gamepad_config),
-                    button_mapper_row("South", Button::South, single_active_gamepad_config),
+                    button_row("North", Button::North),
+                    button_row("West", Button::West),
+                    button_row("East", Button::East),
+                    button_row("South", Button::South),
                     header("Sticks"),
-                    button_mapper_row(
-                        "Left Stick Press",
-                        Button::LeftThumb,
-                        single_active_gamepad_config
-                    ),
-                    button_mapper_row(
-                        "Right Stick Press",
-                        Button::RightThumb,
-                        single_active_gamepad_config
-                    ),
+                    button_row("Left Stick Press", Button::LeftThumb),
+                    button_row("Right Stick Press", Button::RightThumb),
                     header("Triggers"),
-                    button_mapper_row(
-                        "Left Bumper",
-                        Button::LeftTrigger,
-                        single_active_gamepad_config
-                    ),
-                    button_mapper_row(
-                        "Left Trigger",
-                        Button::LeftTrigger2,
-                        single_active_gamepad_config
-                    ),
-                    button_mapper_row(
-                        "Right Bumper",
-                        Button::RightTrigger,
-                        single_active_gamepad_config
-                    ),
-                    button_mapper_row(
-                        "Right Trigger",
-                        Button::RightTrigger2,
-                        single_active_gamepad_config
-                    ),
+                    button_row("Left Bumper", Button::LeftTrigger),
+                    button_row("Left Trigger", Button::LeftTrigger2),
+                    button_row("Right Bumper", Button::RightTrigger),
+                    button_row("Right Trigger", Button::RightTrigger2),
+                    header("Axes"),
+                    axis_bindings(gamepad_config),
                     header("D-Pad"),
-                    button_mapper_row("Up", Button::DPadUp, single_active_gamepad_config),
-                    button_mapper_row("Left", Button::DPadLeft, single_active_gamepad_config),
-                    button_mapper_row("Right", Button::DPadRight, single_active_gamepad_config),
-                    button_mapper_row("Down", Button::DPadDown, single_active_gamepad_config),
+                    button_row("Up", Button::DPadUp),
+                    button_row("Left", Button::DPadLeft),
+                    button_row("Right", Button::DPadRight),
+                    button_row("Down", Button::DPadDown),
                     header("Misc."),
-                    button_mapper_row("C Button", Button::C, single_active_gamepad_config),
-                    button_mapper_row("Z Button", Button::Z, single_active_gamepad_config),
+                    button_row("C Button", Button::C),
+                    button_row("Z Button", Button::Z),
                 ]
                 .spacing(5)
             )
             .height(Length::Fill),
-            row![activate, deactivate, handler_text].spacing(10),
+            row![activate, deactivate, handler_text, handler_status_text].spacing(10),
             row![save_profile, load_profile].spacing(10),
         ]
         .spacing(5)
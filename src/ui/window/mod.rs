@@ -1,6 +1,9 @@
 pub mod base;
 mod components;
 pub mod error_window;
+mod gamepad_config_slot;
 pub mod key_press_window;
+pub mod macro_editor_window;
+pub mod macro_recorder_window;
 pub mod main_window;
 mod mouse_button_wrapper;
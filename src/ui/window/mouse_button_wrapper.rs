@@ -4,6 +4,20 @@ use std::fmt::{Display, Formatter};
 #[derive(Clone, Debug, PartialEq)]
 pub struct MouseButtonWrapper(pub MouseButton);
 
+impl MouseButtonWrapper {
+    pub const ALL: [MouseButtonWrapper; 9] = [
+        MouseButtonWrapper(MouseButton::Left),
+        MouseButtonWrapper(MouseButton::Middle),
+        MouseButtonWrapper(MouseButton::Right),
+        MouseButtonWrapper(MouseButton::Back),
+        MouseButtonWrapper(MouseButton::Forward),
+        MouseButtonWrapper(MouseButton::ScrollUp),
+        MouseButtonWrapper(MouseButton::ScrollDown),
+        MouseButtonWrapper(MouseButton::ScrollLeft),
+        MouseButtonWrapper(MouseButton::ScrollRight),
+    ];
+}
+
 impl Display for MouseButtonWrapper {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self.0 {